@@ -0,0 +1,13 @@
+//! Protobuf messages of the ICS-12 NEAR light client.
+//!
+//! The `ClientState` and `Misbehaviour` messages of
+//! `proto/ibc/lightclients/near/v1/near.proto` extend the ones of
+//! `ics12-proto`, all other messages are re-exported from it.
+
+#![no_std]
+#![forbid(unsafe_code)]
+#![deny(warnings, trivial_casts, trivial_numeric_casts, unused_import_braces)]
+
+pub use prost::{DecodeError, Message};
+
+pub mod v1;
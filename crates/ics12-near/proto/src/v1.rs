@@ -0,0 +1,108 @@
+//! Messages of the `ibc.lightclients.near.v1` package.
+//!
+//! The messages defined here mirror the code generated by `prost-build` 0.12
+//! from `proto/ibc/lightclients/near/v1/near.proto` and must be kept in sync
+//! with it.
+
+pub use ics12_proto::v1::*;
+
+/// How the IBC contract on NEAR derives the keys of its storage from the
+/// commitment prefix and the ICS-24 path of a commitment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum StorageKeyScheme {
+    /// The commitment prefix followed by the path as a string.
+    PlainString = 0,
+    /// Entries of a near-sdk `LookupMap` keyed by the path string, with borsh
+    /// encoded keys and values.
+    LookupMapBorsh = 1,
+}
+
+impl StorageKeyScheme {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            StorageKeyScheme::PlainString => "STORAGE_KEY_SCHEME_PLAIN_STRING",
+            StorageKeyScheme::LookupMapBorsh => "STORAGE_KEY_SCHEME_LOOKUP_MAP_BORSH",
+        }
+    }
+
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "STORAGE_KEY_SCHEME_PLAIN_STRING" => Some(Self::PlainString),
+            "STORAGE_KEY_SCHEME_LOOKUP_MAP_BORSH" => Some(Self::LookupMapBorsh),
+            _ => None,
+        }
+    }
+}
+
+/// ClientState of the NEAR light client.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ClientState {
+    #[prost(message, optional, tag = "1")]
+    pub trusting_period: ::core::option::Option<::ibc_proto::google::protobuf::Duration>,
+    #[prost(message, optional, tag = "2")]
+    pub frozen_height: ::core::option::Option<::ibc_proto::ibc::core::client::v1::Height>,
+    #[prost(message, optional, tag = "3")]
+    pub latest_height: ::core::option::Option<::ibc_proto::ibc::core::client::v1::Height>,
+    #[prost(uint64, tag = "4")]
+    pub latest_timestamp: u64,
+    #[prost(bytes = "vec", tag = "5")]
+    pub upgrade_commitment_prefix: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "6")]
+    pub upgrade_key: ::prost::alloc::vec::Vec<u8>,
+    /// Duration of an epoch of the counterparty chain. Unset means the
+    /// default of the client, 43200 blocks of 2 seconds.
+    #[prost(message, optional, tag = "7")]
+    pub epoch_duration: ::core::option::Option<::ibc_proto::google::protobuf::Duration>,
+    /// Account of the IBC contract. Empty means none.
+    #[prost(string, tag = "8")]
+    pub ibc_contract_account_id: ::prost::alloc::string::String,
+    #[prost(enumeration = "StorageKeyScheme", tag = "9")]
+    pub storage_key_scheme: i32,
+    /// Pinned code hash of the IBC contract, 32 bytes. Empty means none.
+    #[prost(bytes = "vec", tag = "10")]
+    pub ibc_contract_code_hash: ::prost::alloc::vec::Vec<u8>,
+    /// Limits of the state proofs verified by the client. Unset means the
+    /// defaults of the client.
+    #[prost(message, optional, tag = "11")]
+    pub proof_limits: ::core::option::Option<ProofLimits>,
+}
+
+/// Maximum size of the state proofs accepted by the client.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProofLimits {
+    /// Maximum number of trie nodes in a proof, at most 65536.
+    #[prost(uint64, tag = "1")]
+    pub max_nodes: u64,
+    /// Maximum size of an encoded proof in bytes.
+    #[prost(uint64, tag = "2")]
+    pub max_total_bytes: u64,
+    /// Maximum length in bytes of the encoded key of a leaf or extension node.
+    #[prost(uint64, tag = "3")]
+    pub max_key_length: u64,
+    /// Maximum length in nibbles of a key looked up in a proof.
+    #[prost(uint64, tag = "4")]
+    pub max_nibble_depth: u64,
+}
+
+/// Misbehaviour is a wrapper over two conflicting headers.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Misbehaviour {
+    /// ClientID is deprecated
+    #[deprecated]
+    #[prost(string, tag = "1")]
+    pub client_id: ::prost::alloc::string::String,
+    #[prost(message, optional, tag = "2")]
+    pub header_1: ::core::option::Option<Header>,
+    #[prost(message, optional, tag = "3")]
+    pub header_2: ::core::option::Option<Header>,
+    /// Borsh encoded `AncestryProof` linking the lower header to the block at
+    /// the height of the higher one. Empty means none.
+    #[prost(bytes = "vec", tag = "4")]
+    pub ancestry_proof: ::prost::alloc::vec::Vec<u8>,
+}
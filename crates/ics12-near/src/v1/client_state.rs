@@ -1,15 +1,23 @@
+mod execution_outcome;
+mod health;
 mod misbehaviour;
+mod packet;
+mod state_read;
 mod update_client;
 
+pub use health::ClientHealth;
+
 use crate::alloc::string::ToString;
 use crate::v1::consensus_state::ConsensusState as NearConsensusState;
 use crate::v1::context::{
     ExecutionContext as NearExecutionContext, ValidationContext as NearValidationContext,
 };
+use crate::v1::storage_key::StorageKeyEncoding;
 use alloc::format;
 use alloc::vec;
 use alloc::vec::Vec;
 use borsh::BorshDeserialize;
+use core::time::Duration;
 use ibc_core::client::context::client_state::{
     ClientStateCommon, ClientStateExecution, ClientStateValidation,
 };
@@ -25,20 +33,23 @@ use ibc_core::commitment_types::error::CommitmentError;
 use ibc_core::host::types::identifiers::{ClientId, ClientType};
 use ibc_core::host::types::path::Path;
 use ibc_core::host::types::path::{ClientConsensusStatePath, ClientStatePath};
+use ibc_core::primitives::Timestamp;
 use ibc_proto::google::protobuf::Any;
 use ibc_proto::Protobuf;
+use ics12_near_proto::v1::ClientState as RawNearClientState;
 use ics12_near_types::v1::error::Error;
-use ics12_near_types::v1::near_types::hash::sha256;
 use ics12_near_types::v1::near_types::hash::CryptoHash;
 use ics12_near_types::v1::near_types::trie::verify_not_in_state;
 use ics12_near_types::v1::near_types::trie::verify_state_proof;
+use ics12_near_types::v1::near_types::trie::ProofLimits;
+use ics12_near_types::v1::near_types::trie::ProofMode;
 use ics12_near_types::v1::near_types::trie::RawTrieNodeWithSize;
+use ics12_near_types::v1::state_proof::NearStateProof;
 use ics12_near_types::v1::{
     client_state::ClientState as ClientStateType, client_type as near_client_type,
     consensus_state::ConsensusState as ConsensusStateType, header::Header as NearHeader,
     misbehaviour::Misbehaviour as NearMisbehaviour,
 };
-use ics12_proto::v1::ClientState as RawNearClientState;
 use prost::DecodeError;
 
 pub const NEAR_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.near.v1.ClientState";
@@ -57,6 +68,93 @@ impl ClientState {
     pub fn inner(&self) -> &ClientStateType {
         &self.0
     }
+
+    /// Returns `true` if the client has fallen so far behind the chain that no
+    /// header can update it anymore.
+    ///
+    /// This can't be told apart from a long halt of the chain, so it is
+    /// reported by this query rather than by `status`.
+    pub fn is_epoch_stale<V>(&self, ctx: &V, client_id: &ClientId) -> Result<bool, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let latest_consensus_state: NearConsensusState = ctx
+            .consensus_state(&ClientConsensusStatePath::new(
+                client_id.clone(),
+                self.latest_height().revision_number(),
+                self.latest_height().revision_height(),
+            ))?
+            .try_into()
+            .map_err(|err| ClientError::Other {
+                description: err.to_string(),
+            })?;
+
+        let now = ctx.host_timestamp()?;
+        Ok(now
+            .duration_since(&latest_consensus_state.timestamp())
+            .is_some_and(|elapsed| self.0.is_epoch_stale(elapsed)))
+    }
+
+    /// Returns the time left at `now` before `consensus_state` leaves the
+    /// trusting period, or `None` if it already has.
+    ///
+    /// A consensus state whose timestamp is after `now` has the whole trusting
+    /// period left.
+    pub(crate) fn remaining_trusting_period(
+        &self,
+        consensus_state: &NearConsensusState,
+        now: &Timestamp,
+    ) -> Option<Duration> {
+        match now.duration_since(&consensus_state.timestamp()) {
+            Some(elapsed) => self.0.trusting_period.checked_sub(elapsed),
+            None => Some(self.0.trusting_period),
+        }
+    }
+
+    /// Returns the consensus state at `height`, for data of the chain to be
+    /// verified against it. The client must not be frozen, and the consensus
+    /// state must still be within the trusting period.
+    pub(crate) fn verifiable_consensus_state_at<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<NearConsensusState, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        if self.0.is_frozen() {
+            return Err(ClientError::Other {
+                description: format!("Client {} is frozen.", client_id),
+            });
+        }
+
+        let consensus_state: NearConsensusState = ctx
+            .consensus_state(&ClientConsensusStatePath::new(
+                client_id.clone(),
+                height.revision_number(),
+                height.revision_height(),
+            ))?
+            .try_into()
+            .map_err(|err| ClientError::Other {
+                description: err.to_string(),
+            })?;
+
+        let now = ctx.host_timestamp()?;
+        if self
+            .remaining_trusting_period(&consensus_state, &now)
+            .is_none()
+        {
+            return Err(ClientError::Other {
+                description: format!(
+                    "Consensus state at height {} is outside of the trusting period.",
+                    height
+                ),
+            });
+        }
+
+        Ok(consensus_state)
+    }
 }
 
 impl From<ClientStateType> for ClientState {
@@ -158,50 +256,15 @@ impl ClientStateCommon for ClientState {
         path: Path,
         value: Vec<u8>,
     ) -> Result<(), ClientError> {
-        #[derive(BorshDeserialize)]
-        struct Proofs(Vec<Vec<u8>>);
-        let proofs = Proofs::try_from_slice(&Vec::<u8>::from(proof.clone())).map_err(|e| {
-            ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
-                DecodeError::new(format!("Invalid commitment proof: {:?}", e)),
-            ))
-        })?;
-        if proofs.0.is_empty() {
-            return Err(ClientError::InvalidCommitmentProof(
-                CommitmentError::EmptyMerkleProof,
-            ));
-        }
-        let root_hash = CryptoHash(sha256(proofs.0[0].as_ref()));
-        #[derive(BorshDeserialize)]
-        struct StateProofOfChunks(Vec<CryptoHash>);
-        let prev_state_root_of_chunks = StateProofOfChunks::try_from_slice(root.as_bytes())
-            .map_err(|e| {
-                ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
-                    DecodeError::new(format!("Invalid commitment root: {:?}", e)),
-                ))
-            })?;
-        if !prev_state_root_of_chunks.0.contains(&root_hash) {
-            return Err(ClientError::InvalidCommitmentProof(
-                CommitmentError::VerificationFailure,
-            ));
-        }
-        let mut nodes: Vec<RawTrieNodeWithSize> = Vec::new();
-        for proof in &proofs.0 {
-            if let Ok(node) = RawTrieNodeWithSize::decode(proof) {
-                nodes.push(node);
-            } else {
-                return Err(ClientError::InvalidCommitmentProof(
-                    CommitmentError::CommitmentProofDecodingFailed(DecodeError::new(
-                        "Invalid commitment proof: path proof data decode failed.",
-                    )),
-                ));
-            }
-        }
-        let mut key = vec![];
-        key.extend(prefix.as_bytes());
-        key.extend(path.to_string().into_bytes());
-        verify_state_proof(&key, &nodes, &value, &root_hash).map_err(|e| ClientError::Other {
-            description: format!("{:?}", e),
-        })
+        self.verify_membership_with_encoding(
+            &self.0.storage_key_scheme,
+            &self.0.proof_limits,
+            prefix,
+            proof,
+            root,
+            &path,
+            &value,
+        )
     }
 
     fn verify_non_membership(
@@ -211,53 +274,157 @@ impl ClientStateCommon for ClientState {
         root: &CommitmentRoot,
         path: Path,
     ) -> Result<(), ClientError> {
-        #[derive(BorshDeserialize)]
-        struct Proofs(Vec<Vec<u8>>);
-        let proofs = Proofs::try_from_slice(&Vec::<u8>::from(proof.clone())).map_err(|e| {
-            ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
-                DecodeError::new(format!("Invalid commitment proof: {:?}", e)),
-            ))
-        })?;
-        if proofs.0.is_empty() {
-            return Err(ClientError::InvalidCommitmentProof(
-                CommitmentError::EmptyMerkleProof,
-            ));
-        }
-        let root_hash = CryptoHash(sha256(proofs.0[0].as_ref()));
-        #[derive(BorshDeserialize)]
-        struct StateProofOfChunks(Vec<CryptoHash>);
-        let prev_state_root_of_chunks = StateProofOfChunks::try_from_slice(root.as_bytes())
-            .map_err(|e| {
-                ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
-                    DecodeError::new(format!("Invalid commitment root: {:?}", e)),
-                ))
-            })?;
-        if !prev_state_root_of_chunks.0.contains(&root_hash) {
-            return Err(ClientError::InvalidCommitmentProof(
-                CommitmentError::VerificationFailure,
-            ));
-        }
-        let mut nodes: Vec<RawTrieNodeWithSize> = Vec::new();
-        for proof in &proofs.0 {
-            if let Ok(node) = RawTrieNodeWithSize::decode(proof) {
-                nodes.push(node);
-            } else {
-                return Err(ClientError::InvalidCommitmentProof(
-                    CommitmentError::CommitmentProofDecodingFailed(DecodeError::new(
-                        "Invalid commitment proof: path proof data decode failed.",
-                    )),
-                ));
+        self.verify_non_membership_with_encoding(
+            &self.0.storage_key_scheme,
+            &self.0.proof_limits,
+            prefix,
+            proof,
+            root,
+            &path,
+        )
+    }
+}
+
+impl ClientState {
+    /// Returns the trie key of the commitment at `path` under `prefix`, stored
+    /// as described by `encoding`.
+    ///
+    /// With the account id of the IBC contract configured, `prefix` is the
+    /// logical prefix of the commitments in the contract storage. Otherwise it
+    /// is the whole raw prefix of the trie key.
+    pub fn storage_key<E: StorageKeyEncoding>(
+        &self,
+        encoding: &E,
+        prefix: &CommitmentPrefix,
+        path: &Path,
+    ) -> Vec<u8> {
+        self.0
+            .contract_storage_key(&encoding.storage_key(prefix.as_bytes(), path))
+    }
+
+    /// Checks that the IBC contract still has the code pinned in the client
+    /// state, if any, in the state under `state_root`.
+    fn verify_contract_code_hash(
+        &self,
+        state_proof: &NearStateProof,
+        state_root: &CryptoHash,
+        limits: &ProofLimits,
+    ) -> Result<(), ClientError> {
+        let Some(expected_code_hash) = &self.0.ibc_contract_code_hash else {
+            return Ok(());
+        };
+        let account_id =
+            self.0
+                .ibc_contract_account_id
+                .as_ref()
+                .ok_or_else(|| ClientError::Other {
+                    description:
+                        "IBC contract code hash is pinned without IBC contract account id."
+                            .to_string(),
+                })?;
+        Ok(state_proof.verify_contract_code_hash_with_limits(
+            account_id,
+            expected_code_hash,
+            state_root,
+            limits,
+        )?)
+    }
+
+    /// Verifies the membership of a commitment stored as described by
+    /// `encoding`, instead of the storage key scheme of the client state, with
+    /// a proof within `limits`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_membership_with_encoding<E: StorageKeyEncoding>(
+        &self,
+        encoding: &E,
+        limits: &ProofLimits,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        root: &CommitmentRoot,
+        path: &Path,
+        value: &[u8],
+    ) -> Result<(), ClientError> {
+        let (state_proof, root_hash, nodes) = decode_state_proof(proof, root, limits)?;
+        self.verify_contract_code_hash(&state_proof, &root_hash, limits)?;
+        let key = self.storage_key(encoding, prefix, path);
+        limits.check_key(&key)?;
+        let value = encoding.encode_value(path, value);
+        verify_state_proof(&key, &nodes, &value, &root_hash, ProofMode::Strict).map_err(|e| {
+            ClientError::Other {
+                description: format!("{:?}", e),
+            }
+        })
+    }
+
+    /// Verifies the non-membership of a commitment stored as described by
+    /// `encoding`, instead of the storage key scheme of the client state, with
+    /// a proof within `limits`.
+    pub fn verify_non_membership_with_encoding<E: StorageKeyEncoding>(
+        &self,
+        encoding: &E,
+        limits: &ProofLimits,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        root: &CommitmentRoot,
+        path: &Path,
+    ) -> Result<(), ClientError> {
+        let (state_proof, root_hash, nodes) = decode_state_proof(proof, root, limits)?;
+        self.verify_contract_code_hash(&state_proof, &root_hash, limits)?;
+        let key = self.storage_key(encoding, prefix, path);
+        limits.check_key(&key)?;
+        verify_not_in_state(&key, &nodes, &root_hash, ProofMode::Strict).map_err(|e| {
+            ClientError::Other {
+                description: format!("{:?}", e),
             }
-        }
-        let mut key = vec![];
-        key.extend(prefix.as_bytes());
-        key.extend(path.to_string().into_bytes());
-        verify_not_in_state(&key, &nodes, &root_hash).map_err(|e| ClientError::Other {
-            description: format!("{:?}", e),
         })
     }
 }
 
+/// Decodes a `NearStateProof` within `limits` and checks that it is for the
+/// state root of its shard in `root`, returning the proof along with that
+/// state root and its trie nodes.
+fn decode_state_proof(
+    proof: &CommitmentProofBytes,
+    root: &CommitmentRoot,
+    limits: &ProofLimits,
+) -> Result<(NearStateProof, CryptoHash, Vec<RawTrieNodeWithSize>), ClientError> {
+    let bytes = Vec::<u8>::from(proof.clone());
+    let state_proof = NearStateProof::decode_with_limits(&bytes, limits).map_err(|e| {
+        ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
+            DecodeError::new(format!("Invalid commitment proof: {:?}", e)),
+        ))
+    })?;
+    let nodes = state_proof
+        .validate_with_limits(limits)
+        .map_err(|e| match e {
+            Error::MissingProofData => {
+                ClientError::InvalidCommitmentProof(CommitmentError::EmptyMerkleProof)
+            }
+            e @ (Error::TooManyProofNodes { .. } | Error::ProofNodeKeyTooLong { .. }) => {
+                ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
+                    DecodeError::new(format!("Invalid commitment proof: {:?}", e)),
+                ))
+            }
+            _ => {
+                ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
+                    DecodeError::new("Invalid commitment proof: path proof data decode failed."),
+                ))
+            }
+        })?;
+    #[derive(BorshDeserialize)]
+    struct StateProofOfChunks(Vec<CryptoHash>);
+    let prev_state_root_of_chunks =
+        StateProofOfChunks::try_from_slice(root.as_bytes()).map_err(|e| {
+            ClientError::InvalidCommitmentProof(CommitmentError::CommitmentProofDecodingFailed(
+                DecodeError::new(format!("Invalid commitment root: {:?}", e)),
+            ))
+        })?;
+    let root_hash = state_proof
+        .verify_state_root(&prev_state_root_of_chunks.0)
+        .map_err(|_| ClientError::InvalidCommitmentProof(CommitmentError::VerificationFailure))?;
+    Ok((state_proof, root_hash, nodes))
+}
+
 impl<V> ClientStateValidation<V> for ClientState
 where
     V: NearValidationContext + ClientValidationContext,
@@ -323,16 +490,12 @@ where
             any_latest_consensus_state.try_into()?
         };
 
-        // Note: if the `duration_since()` is `None`, indicating that the latest
-        // consensus state is in the future, then we don't consider the client
-        // to be expired.
         let now = ctx.host_timestamp()?;
-        if let Some(elapsed_since_latest_consensus_state) =
-            now.duration_since(&latest_consensus_state.timestamp())
+        if self
+            .remaining_trusting_period(&latest_consensus_state, &now)
+            .is_none()
         {
-            if elapsed_since_latest_consensus_state > self.0.trusting_period {
-                return Ok(Status::Expired);
-            }
+            return Ok(Status::Expired);
         }
 
         Ok(Status::Active)
@@ -0,0 +1,103 @@
+use super::ClientState;
+use crate::v1::context::ValidationContext as NearValidationContext;
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::host::types::identifiers::ClientId;
+use ics12_near_types::v1::near_types::event::Nep297Event;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::light_client_proof::{
+    ExecutionOutcomeProof, ReceiptProof, TransactionProof, VerifiedReceipt,
+};
+use ics12_near_types::v1::near_types::AccountId;
+
+impl ClientState {
+    /// Verifies that the execution outcome of `proof` was included in a block
+    /// of the chain before the one of the consensus state at `height`.
+    ///
+    /// Applications can then act on the result of a transaction or receipt
+    /// executed on NEAR, such as its status, logs or generated receipts.
+    pub fn verify_execution_outcome<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        proof: &ExecutionOutcomeProof,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let block_merkle_root = self.block_merkle_root_at(ctx, client_id, height)?;
+        Ok(proof.verify(&block_merkle_root)?)
+    }
+
+    /// Verifies that `executor_id` emitted the NEP-297 event of the log at
+    /// `log_index` of the execution outcome of `proof`, in a block of the
+    /// chain before the one of the consensus state at `height`.
+    pub fn verify_event_log<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        proof: &ExecutionOutcomeProof,
+        executor_id: &AccountId,
+        log_index: usize,
+    ) -> Result<Nep297Event, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let block_merkle_root = self.block_merkle_root_at(ctx, client_id, height)?;
+        Ok(proof.verify_event(&block_merkle_root, executor_id, log_index)?)
+    }
+
+    /// Verifies that the transaction of `proof` was executed in a block of the
+    /// chain before the one of the consensus state at `height`.
+    pub fn verify_transaction<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        proof: &TransactionProof,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let block_merkle_root = self.block_merkle_root_at(ctx, client_id, height)?;
+        Ok(proof.verify(&block_merkle_root)?)
+    }
+
+    /// Verifies that the receipt of `proof` was created and executed in blocks
+    /// of the chain before the one of the consensus state at `height`, and
+    /// returns what the proof establishes about the receipt. See
+    /// [`ReceiptProof`] for how to also learn its actions.
+    pub fn verify_receipt<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        proof: &ReceiptProof,
+    ) -> Result<VerifiedReceipt, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let block_merkle_root = self.block_merkle_root_at(ctx, client_id, height)?;
+        Ok(proof.verify(&block_merkle_root)?)
+    }
+
+    fn block_merkle_root_at<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<CryptoHash, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        Ok(self
+            .verifiable_consensus_state_at(ctx, client_id, height)?
+            .inner()
+            .header
+            .light_client_block
+            .inner_lite
+            .block_merkle_root)
+    }
+}
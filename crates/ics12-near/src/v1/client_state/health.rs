@@ -0,0 +1,105 @@
+use super::ClientState;
+use crate::v1::consensus_state::ConsensusState as NearConsensusState;
+use crate::v1::context::ValidationContext as NearValidationContext;
+use core::time::Duration;
+use ibc_core::channel::types::error::ChannelError;
+use ibc_core::client::context::client_state::{ClientStateCommon, ClientStateValidation};
+use ibc_core::client::context::consensus_state::ConsensusState;
+use ibc_core::client::context::ClientValidationContext;
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::{Height, Status};
+use ibc_core::handler::types::error::ContextError;
+use ibc_core::host::types::identifiers::ClientId;
+use ibc_core::host::types::path::ClientConsensusStatePath;
+use ibc_core::primitives::Timestamp;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+
+/// Health report of a NEAR client, for monitoring purposes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientHealth {
+    /// Status of the client
+    pub status: Status,
+    /// Time left before the trusting period of the latest consensus state
+    /// expires, zero if it already has
+    pub remaining_trusting_period: Duration,
+    /// Latest height the client was updated to
+    pub latest_height: Height,
+    /// Timestamp of the latest consensus state
+    pub latest_timestamp: Timestamp,
+    /// Epoch id of the latest consensus state
+    pub epoch_id: CryptoHash,
+    /// Whether the block producers of the next epoch are known, which is
+    /// required to follow the chain into the next epoch
+    pub next_bps_known: bool,
+    /// Whether the client has fallen too far behind the chain to be updated
+    pub epoch_stale: bool,
+    /// Number of consensus states stored for the client, `None` if the host
+    /// can not list them
+    pub consensus_state_count: Option<usize>,
+    /// Host time of the update to the latest height, `None` if the client was
+    /// never updated since its creation
+    pub last_update_time: Option<Timestamp>,
+    /// Host height of the update to the latest height, `None` if the client
+    /// was never updated since its creation
+    pub last_update_height: Option<Height>,
+}
+
+impl ClientState {
+    /// Collects the health report of the client.
+    pub fn health<V>(&self, ctx: &V, client_id: &ClientId) -> Result<ClientHealth, ClientError>
+    where
+        V: NearValidationContext + ClientValidationContext,
+        V::AnyConsensusState: TryInto<NearConsensusState>,
+        ClientError: From<<V::AnyConsensusState as TryInto<NearConsensusState>>::Error>,
+    {
+        let status = ClientStateValidation::<V>::status(self, ctx, client_id)?;
+        let latest_height = self.latest_height();
+
+        let latest_consensus_state: NearConsensusState = ctx
+            .consensus_state(&ClientConsensusStatePath::new(
+                client_id.clone(),
+                latest_height.revision_number(),
+                latest_height.revision_height(),
+            ))?
+            .try_into()?;
+        let latest_header = &latest_consensus_state.inner().header;
+
+        let now = ctx.host_timestamp()?;
+        let remaining_trusting_period = self
+            .remaining_trusting_period(&latest_consensus_state, &now)
+            .unwrap_or(Duration::ZERO);
+
+        Ok(ClientHealth {
+            status,
+            remaining_trusting_period,
+            latest_height,
+            latest_timestamp: latest_consensus_state.timestamp(),
+            epoch_id: latest_header.epoch_id(),
+            next_bps_known: latest_consensus_state
+                .inner()
+                .get_block_producers_of(&latest_header.next_epoch_id())
+                .is_some(),
+            epoch_stale: self.is_epoch_stale(ctx, client_id)?,
+            consensus_state_count: ctx
+                .consensus_state_heights(client_id)?
+                .map(|heights| heights.len()),
+            last_update_time: not_found_as_none(ctx.client_update_time(client_id, &latest_height))?,
+            last_update_height: not_found_as_none(
+                ctx.client_update_height(client_id, &latest_height),
+            )?,
+        })
+    }
+}
+
+/// Returns `None` if the host has no processed time or height for an update,
+/// and propagates any other error of the host.
+fn not_found_as_none<T>(result: Result<T, ContextError>) -> Result<Option<T>, ClientError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(ContextError::ChannelError(
+            ChannelError::ProcessedTimeNotFound { .. }
+            | ChannelError::ProcessedHeightNotFound { .. },
+        )) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
@@ -1,9 +1,14 @@
 use super::ClientState as NearClientState;
+use crate::v1::consensus_state::ConsensusState as NearConsensusState;
 use crate::v1::context::ValidationContext as NearValidationContext;
+use alloc::format;
+use alloc::string::ToString;
+use alloc::vec::Vec;
 use ibc_core::client::types::error::ClientError;
 use ibc_core::host::types::identifiers::ClientId;
-// use ics12_near_types::v1::consensus_state::ConsensusState as NearConsensusState;
-use ics12_near_types::v1::misbehaviour::Misbehaviour as NearMisbehaviour;
+use ics12_near_types::v1::header::Header as NearHeader;
+use ics12_near_types::v1::misbehaviour::{EquivocationEvidence, Misbehaviour as NearMisbehaviour};
+use ics12_near_types::v1::near_types::ValidatorStakeView;
 
 impl NearClientState {
     // verify_misbehaviour determines whether or not two conflicting headers at
@@ -17,30 +22,113 @@ impl NearClientState {
     where
         ClientValidationContext: NearValidationContext,
     {
-        self.verify_header(ctx, client_id, misbehaviour.header1())?;
-        self.verify_header(ctx, client_id, misbehaviour.header2())
+        if misbehaviour.client_id() != client_id {
+            return Err(ClientError::Other {
+                description: format!(
+                    "Misbehaviour is for client {} instead of {}.",
+                    misbehaviour.client_id(),
+                    client_id
+                ),
+            });
+        }
+
+        // Each header is verified against the consensus state trusted at its
+        // own height, so that evidence of a past epoch can be checked as well.
+        for header in [misbehaviour.header1(), misbehaviour.header2()] {
+            let trusted_consensus_state =
+                self.trusted_consensus_state_of(ctx, client_id, header)?;
+            self.verify_header_with_trusted_state(trusted_consensus_state.inner(), header)?;
+        }
+
+        Ok(misbehaviour.verify_ancestry_proof()?)
     }
 
     pub fn check_for_misbehaviour_misbehaviour(
         &self,
         misbehaviour: &NearMisbehaviour,
     ) -> Result<bool, ClientError> {
-        let header_1 = misbehaviour.header1();
-        let header_2 = misbehaviour.header2();
-
-        if header_1.height() == header_2.height() {
-            // when the height of the 2 headers are equal, we only have evidence
-            // of misbehaviour in the case where the headers are different
-            // (otherwise, the same header was added twice in the message,
-            // and this is evidence of nothing)
-            Ok(header_1.light_client_block.current_block_hash()
-                != header_2.light_client_block.current_block_hash())
-        } else {
-            // header_1 is at greater height than header_2, therefore
-            // header_1 time must be less than or equal to
-            // header_2 time in order to be valid misbehaviour (violation of
-            // monotonic time).
-            Ok(header_1.timestamp() <= header_2.timestamp())
+        Ok(misbehaviour.kind().is_some())
+    }
+
+    /// Returns the block producers which signed both conflicting headers of a
+    /// verified misbehaviour, along with their stake.
+    pub fn equivocation_evidence<ClientValidationContext>(
+        &self,
+        ctx: &ClientValidationContext,
+        client_id: &ClientId,
+        misbehaviour: &NearMisbehaviour,
+    ) -> Result<EquivocationEvidence, ClientError>
+    where
+        ClientValidationContext: NearValidationContext,
+    {
+        self.verify_misbehaviour(ctx, client_id, misbehaviour.clone())?;
+
+        let header1_bps =
+            self.trusted_block_producers_of(ctx, client_id, misbehaviour.header1())?;
+        let header2_bps =
+            self.trusted_block_producers_of(ctx, client_id, misbehaviour.header2())?;
+
+        Ok(misbehaviour.equivocation_evidence(&header1_bps, &header2_bps)?)
+    }
+
+    fn trusted_block_producers_of<ClientValidationContext>(
+        &self,
+        ctx: &ClientValidationContext,
+        client_id: &ClientId,
+        header: &NearHeader,
+    ) -> Result<Vec<ValidatorStakeView>, ClientError>
+    where
+        ClientValidationContext: NearValidationContext,
+    {
+        self.trusted_consensus_state_of(ctx, client_id, header)?
+            .inner()
+            .get_block_producers_of(&header.epoch_id())
+            .ok_or_else(|| ClientError::Other {
+                description: format!(
+                    "Trusted consensus state is invalid: missing epoch block producers for epoch {}.",
+                    header.epoch_id()
+                ),
+            })
+    }
+
+    /// Returns the consensus state `header` is verified against when it is part
+    /// of a misbehaviour: the highest one below the height of the header, which
+    /// must still be within the trusting period.
+    pub(crate) fn trusted_consensus_state_of<ClientValidationContext>(
+        &self,
+        ctx: &ClientValidationContext,
+        client_id: &ClientId,
+        header: &NearHeader,
+    ) -> Result<NearConsensusState, ClientError>
+    where
+        ClientValidationContext: NearValidationContext,
+    {
+        let trusted_consensus_state: NearConsensusState = ctx
+            .prev_consensus_state(client_id, &header.height())?
+            .ok_or_else(|| ClientError::Other {
+                description: format!(
+                    "No trusted consensus state below height {}.",
+                    header.height()
+                ),
+            })?
+            .try_into()
+            .map_err(|err| ClientError::Other {
+                description: err.to_string(),
+            })?;
+
+        let now = ctx.host_timestamp()?;
+        if self
+            .remaining_trusting_period(&trusted_consensus_state, &now)
+            .is_none()
+        {
+            return Err(ClientError::Other {
+                description: format!(
+                    "Trusted consensus state at height {} is outside of the trusting period.",
+                    trusted_consensus_state.inner().header.height()
+                ),
+            });
         }
+
+        Ok(trusted_consensus_state)
     }
 }
@@ -0,0 +1,183 @@
+use super::ClientState;
+use crate::v1::context::ValidationContext as NearValidationContext;
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
+use ibc_core::channel::types::commitment::{compute_ack_commitment, compute_packet_commitment};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::client::context::consensus_state::ConsensusState;
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::{
+    CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+};
+use ibc_core::host::types::identifiers::{ChannelId, ClientId, PortId, Sequence};
+use ibc_core::host::types::path::{AckPath, CommitmentPath, Path, ReceiptPath, SeqRecvPath};
+
+impl ClientState {
+    /// Verifies that the IBC contract on NEAR committed to `packet`, sent by
+    /// it, in the state of the consensus state at `height`.
+    ///
+    /// `prefix` is the commitment prefix of the connection end on NEAR, and
+    /// the commitment is the one ibc-rs stores for the packet.
+    pub fn verify_packet_commitment<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        packet: &Packet,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let path = Path::Commitment(CommitmentPath {
+            port_id: packet.port_id_on_a.clone(),
+            channel_id: packet.chan_id_on_a.clone(),
+            sequence: packet.seq_on_a,
+        });
+        let commitment = compute_packet_commitment(
+            &packet.data,
+            &packet.timeout_height_on_b,
+            &packet.timeout_timestamp_on_b,
+        );
+        self.verify_membership_at(
+            ctx,
+            client_id,
+            height,
+            prefix,
+            proof,
+            &path,
+            &commitment.into_vec(),
+        )
+    }
+
+    /// Verifies that the IBC contract on NEAR wrote `ack` for `packet`,
+    /// received by it, in the state of the consensus state at `height`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_packet_acknowledgement<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        packet: &Packet,
+        ack: &Acknowledgement,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let path = Path::Ack(AckPath {
+            port_id: packet.port_id_on_b.clone(),
+            channel_id: packet.chan_id_on_b.clone(),
+            sequence: packet.seq_on_a,
+        });
+        self.verify_membership_at(
+            ctx,
+            client_id,
+            height,
+            prefix,
+            proof,
+            &path,
+            &compute_ack_commitment(ack).into_vec(),
+        )
+    }
+
+    /// Verifies that the IBC contract on NEAR has no receipt of `packet` in
+    /// the state of the consensus state at `height`, i.e. that the packet
+    /// was not received on an unordered channel and can time out.
+    pub fn verify_packet_receipt_absence<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        packet: &Packet,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let path = Path::Receipt(ReceiptPath {
+            port_id: packet.port_id_on_b.clone(),
+            channel_id: packet.chan_id_on_b.clone(),
+            sequence: packet.seq_on_a,
+        });
+        let root = self.commitment_root_at(ctx, client_id, height)?;
+        self.verify_non_membership_with_encoding(
+            &self.0.storage_key_scheme,
+            &self.0.proof_limits,
+            prefix,
+            proof,
+            &root,
+            &path,
+        )
+    }
+
+    /// Verifies that the next sequence to receive on the channel `channel_id`
+    /// of the port `port_id` of the IBC contract on NEAR is `next_seq_recv`
+    /// in the state of the consensus state at `height`, as for the timeout of
+    /// a packet on an ordered channel.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_next_sequence_recv<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        port_id: &PortId,
+        channel_id: &ChannelId,
+        next_seq_recv: Sequence,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let path = Path::SeqRecv(SeqRecvPath(port_id.clone(), channel_id.clone()));
+        // The value is the big endian `u64` stored by ibc-rs, which the
+        // storage key scheme encodes as the IBC contract stores it.
+        let value = u64::from(next_seq_recv).to_be_bytes();
+        self.verify_membership_at(ctx, client_id, height, prefix, proof, &path, &value)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn verify_membership_at<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        prefix: &CommitmentPrefix,
+        proof: &CommitmentProofBytes,
+        path: &Path,
+        value: &[u8],
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let root = self.commitment_root_at(ctx, client_id, height)?;
+        self.verify_membership_with_encoding(
+            &self.0.storage_key_scheme,
+            &self.0.proof_limits,
+            prefix,
+            proof,
+            &root,
+            path,
+            value,
+        )
+    }
+
+    fn commitment_root_at<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+    ) -> Result<CommitmentRoot, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        Ok(self
+            .verifiable_consensus_state_at(ctx, client_id, height)?
+            .root()
+            .clone())
+    }
+}
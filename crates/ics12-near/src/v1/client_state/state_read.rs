@@ -0,0 +1,141 @@
+use super::ClientState;
+use crate::v1::context::ValidationContext as NearValidationContext;
+use alloc::vec::Vec;
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::CommitmentProofBytes;
+use ibc_core::host::types::identifiers::ClientId;
+use ics12_near_types::v1::near_types::get_raw_prefix_for_contract_data;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::trie::{
+    get_value_ref, verify_not_in_state, verify_state_proof, verify_state_proof_of_value_ref,
+    ProofLimits, ProofMode, RawTrieNodeWithSize, ValueRef,
+};
+use ics12_near_types::v1::near_types::AccountId;
+use ics12_near_types::v1::state_proof::NearStateProof;
+
+impl ClientState {
+    /// Verifies that the storage of the contract `account_id` holds `value`
+    /// under `key` in the state of the consensus state at `height`, or has no
+    /// value under `key` if `value` is `None`.
+    ///
+    /// `key` is the raw key of the contract storage, as used by the contract,
+    /// and `proof` is a [`NearStateProof`]. Unlike `verify_membership`, this
+    /// reads any contract, not only the IBC one, and does not go through the
+    /// storage key scheme of the client state. The proof and the key are
+    /// checked against `limits`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_contract_storage<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        account_id: &AccountId,
+        key: &[u8],
+        value: Option<&[u8]>,
+        proof: &CommitmentProofBytes,
+        limits: &ProofLimits,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let (trie_key, nodes, state_root) =
+            self.contract_storage_proof(ctx, client_id, height, account_id, key, proof, limits)?;
+        match value {
+            Some(value) => {
+                verify_state_proof(&trie_key, &nodes, value, &state_root, ProofMode::Strict)?
+            }
+            None => verify_not_in_state(&trie_key, &nodes, &state_root, ProofMode::Strict)?,
+        }
+        Ok(())
+    }
+
+    /// Verifies that the storage of the contract `account_id` holds the value
+    /// referred to by `value_ref` under `key`, as `verify_contract_storage`
+    /// does for the value itself.
+    ///
+    /// Only the hash and length of the value are needed, so that a large
+    /// value can be bound to the state without being sent to the host.
+    #[allow(clippy::too_many_arguments)]
+    pub fn verify_contract_storage_value_ref<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        account_id: &AccountId,
+        key: &[u8],
+        value_ref: &ValueRef,
+        proof: &CommitmentProofBytes,
+        limits: &ProofLimits,
+    ) -> Result<(), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let (trie_key, nodes, state_root) =
+            self.contract_storage_proof(ctx, client_id, height, account_id, key, proof, limits)?;
+        verify_state_proof_of_value_ref(
+            &trie_key,
+            &nodes,
+            value_ref,
+            &state_root,
+            ProofMode::Strict,
+        )?;
+        Ok(())
+    }
+
+    /// Returns the reference to the value under `key` in the storage of the
+    /// contract `account_id`, as proven by `proof` in the state of the
+    /// consensus state at `height`, or `None` if `key` has no value.
+    #[allow(clippy::too_many_arguments)]
+    pub fn contract_storage_value_ref<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        account_id: &AccountId,
+        key: &[u8],
+        proof: &CommitmentProofBytes,
+        limits: &ProofLimits,
+    ) -> Result<Option<ValueRef>, ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let (trie_key, nodes, state_root) =
+            self.contract_storage_proof(ctx, client_id, height, account_id, key, proof, limits)?;
+        Ok(get_value_ref(
+            &trie_key,
+            &nodes,
+            &state_root,
+            ProofMode::Strict,
+        )?)
+    }
+
+    /// Returns the trie key of `key` in the storage of the contract
+    /// `account_id`, with the nodes of `proof` and the state root they are
+    /// verified against, checked against `limits`.
+    #[allow(clippy::too_many_arguments)]
+    fn contract_storage_proof<V>(
+        &self,
+        ctx: &V,
+        client_id: &ClientId,
+        height: &Height,
+        account_id: &AccountId,
+        key: &[u8],
+        proof: &CommitmentProofBytes,
+        limits: &ProofLimits,
+    ) -> Result<(Vec<u8>, Vec<RawTrieNodeWithSize>, CryptoHash), ClientError>
+    where
+        V: NearValidationContext,
+    {
+        let consensus_state = self.verifiable_consensus_state_at(ctx, client_id, height)?;
+        let bytes = Vec::<u8>::from(proof.clone());
+        let state_proof = NearStateProof::decode_with_limits(&bytes, limits)?;
+        let state_root = state_proof
+            .verify_state_root(&consensus_state.inner().header.prev_state_root_of_chunks)?;
+        let nodes = state_proof.validate_with_limits(limits)?;
+
+        let trie_key = get_raw_prefix_for_contract_data(account_id, key);
+        limits.check_key(&trie_key)?;
+        Ok((trie_key, nodes, state_root))
+    }
+}
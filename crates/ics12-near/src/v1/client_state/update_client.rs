@@ -7,6 +7,7 @@ use borsh::to_vec;
 use ibc_core::client::types::error::ClientError;
 use ibc_core::host::types::identifiers::ClientId;
 use ibc_core::host::types::path::ClientConsensusStatePath;
+use ics12_near_types::v1::consensus_state::ConsensusState as ConsensusStateType;
 use ics12_near_types::v1::header::Header as NearHeader;
 use ics12_near_types::v1::near_types::{hash::sha256, merkle::merklize};
 
@@ -32,12 +33,23 @@ impl ClientState {
             .map_err(|err| ClientError::Other {
                 description: err.to_string(),
             })?;
-        let latest_header = &latest_consensus_state.inner().header;
+
+        self.verify_header_with_trusted_state(latest_consensus_state.inner(), header)
+    }
+
+    /// Verifies `header` against `trusted_consensus_state`, which must know the
+    /// block producers of the epoch of the header.
+    pub fn verify_header_with_trusted_state(
+        &self,
+        trusted_consensus_state: &ConsensusStateType,
+        header: &NearHeader,
+    ) -> Result<(), ClientError> {
+        let trusted_header = &trusted_consensus_state.header;
 
         let approval_message = header.light_client_block.approval_message();
 
         // Check the height of the block is in the same epoch with the current head.
-        if header.height().add(43200) <= latest_header.height() {
+        if header.height().add(43200) <= trusted_header.height() {
             return Err(ClientError::Other {
                 description: "Header is too old.".to_string(),
             });
@@ -45,8 +57,8 @@ impl ClientState {
 
         // Check the epoch of the block is equal to the epoch_id or next_epoch_id
         // known for the current head.
-        if header.epoch_id() != latest_header.epoch_id()
-            && header.epoch_id() != latest_header.next_epoch_id()
+        if header.epoch_id() != trusted_header.epoch_id()
+            && header.epoch_id() != trusted_header.next_epoch_id()
         {
             return Err(ClientError::Other {
                 description: "Invalid epoch id in header.".to_string(),
@@ -55,7 +67,7 @@ impl ClientState {
 
         // If the epoch of the block is equal to the next_epoch_id of the head,
         // then next_bps is not None.
-        if header.epoch_id() == latest_header.next_epoch_id()
+        if header.epoch_id() == trusted_header.next_epoch_id()
             && header.light_client_block.next_bps.is_none()
         {
             return Err(ClientError::Other {
@@ -70,13 +82,11 @@ impl ClientState {
         let mut total_stake = 0;
         let mut approved_stake = 0;
 
-        let bps = latest_consensus_state
-            .inner()
-            .get_block_producers_of(&header.epoch_id());
+        let bps = trusted_consensus_state.get_block_producers_of(&header.epoch_id());
         if bps.is_none() {
             return Err(ClientError::Other {
                 description: format!(
-                    "Latest consensus state is invalid: missing epoch block producers for epoch {}.",
+                    "Trusted consensus state is invalid: missing epoch block producers for epoch {}.",
                     header.epoch_id()
                 )
             });
@@ -209,6 +219,12 @@ impl ClientState {
                         if header.timestamp() <= prev_cs.inner().header.timestamp() {
                             return Ok(true);
                         }
+
+                        // The new header cannot commit to other block producers
+                        // for the same next epoch as the previous consensus state.
+                        if header.has_conflicting_next_bps(&prev_cs.inner().header) {
+                            return Ok(true);
+                        }
                     }
                 }
 
@@ -229,6 +245,10 @@ impl ClientState {
                         if header.timestamp() >= next_cs.inner().header.timestamp() {
                             return Ok(true);
                         }
+
+                        if header.has_conflicting_next_bps(&next_cs.inner().header) {
+                            return Ok(true);
+                        }
                     }
                 }
 
@@ -3,9 +3,9 @@ use ibc_core::client::types::error::ClientError;
 use ibc_core::commitment_types::commitment::CommitmentRoot;
 use ibc_core::primitives::Timestamp;
 use ibc_proto::{google::protobuf::Any, Protobuf};
+use ics12_near_proto::v1::ConsensusState as RawNearConsensusState;
 use ics12_near_types::v1::consensus_state::ConsensusState as ConsensusStateType;
 use ics12_near_types::v1::error::Error;
-use ics12_proto::v1::ConsensusState as RawNearConsensusState;
 
 pub const NEAR_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.lightclients.near.v1.ConsensusState";
 
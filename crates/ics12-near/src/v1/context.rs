@@ -45,6 +45,18 @@ pub trait ValidationContext: CommonContext {
         client_id: &ClientId,
         height: &Height,
     ) -> Result<Option<Self::AnyConsensusState>, ContextError>;
+
+    /// Returns the heights of all consensus states stored for the client, or
+    /// `None` if the host can not list them.
+    ///
+    /// Only used for the health report of the client. The default
+    /// implementation returns `None`, so that hosts need not implement it.
+    fn consensus_state_heights(
+        &self,
+        _client_id: &ClientId,
+    ) -> Result<Option<Vec<Height>>, ContextError> {
+        Ok(None)
+    }
 }
 
 /// Client's context required during execution.
@@ -0,0 +1,189 @@
+//! Offline detection of misbehaviour, for relayers and watchers.
+//!
+//! The detector follows the chain with the headers of a primary source and
+//! checks the headers of witness sources against them. Headers are verified
+//! with the same logic as [`ClientState::verify_header`], and conflicts are
+//! decided and labelled with [`NearMisbehaviour::kind`], as in
+//! [`ClientState::check_for_misbehaviour_misbehaviour`], so that the
+//! misbehaviour it builds is accepted by the client on the host chain.
+//!
+//! Headers at different heights on different forks are only detected as such
+//! when a witness provides an [`AncestryProof`] along with its header, with
+//! [`MisbehaviourDetector::check_witness_header_with_ancestry_proof`].
+//! Otherwise, they are only detected when their timestamps conflict.
+
+use crate::v1::client_state::ClientState;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::vec::Vec;
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::host::types::identifiers::ClientId;
+use ibc_proto::google::protobuf::Any;
+use ics12_near_types::v1::consensus_state::ConsensusState as ConsensusStateType;
+use ics12_near_types::v1::header::Header as NearHeader;
+use ics12_near_types::v1::misbehaviour::{AncestryProof, Misbehaviour as NearMisbehaviour};
+use ics12_near_types::v1::near_types::ValidatorStakeView;
+
+pub use ics12_near_types::v1::misbehaviour::MisbehaviourKind;
+
+/// A misbehaviour found by the [`MisbehaviourDetector`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct DetectedMisbehaviour {
+    pub kind: MisbehaviourKind,
+    pub misbehaviour: NearMisbehaviour,
+}
+
+impl DetectedMisbehaviour {
+    /// Returns the misbehaviour encoded as a client message, ready to be
+    /// submitted to the host chain.
+    pub fn to_any(&self) -> Any {
+        self.misbehaviour.clone().into()
+    }
+}
+
+/// Detects misbehaviour by comparing the headers of witness sources with the
+/// ones of a primary source.
+pub struct MisbehaviourDetector {
+    client_id: ClientId,
+    client_state: ClientState,
+    /// Consensus states of the verified headers of the primary source
+    trusted_consensus_states: BTreeMap<Height, ConsensusStateType>,
+}
+
+impl MisbehaviourDetector {
+    /// Creates a detector for the client `client_id`, starting from a trusted
+    /// consensus state of it.
+    pub fn new(
+        client_id: ClientId,
+        client_state: ClientState,
+        trusted_consensus_state: ConsensusStateType,
+    ) -> Self {
+        let mut trusted_consensus_states = BTreeMap::new();
+        trusted_consensus_states.insert(
+            trusted_consensus_state.header.height(),
+            trusted_consensus_state,
+        );
+        Self {
+            client_id,
+            client_state,
+            trusted_consensus_states,
+        }
+    }
+
+    /// Returns the heights of the headers trusted so far.
+    pub fn trusted_heights(&self) -> Vec<Height> {
+        self.trusted_consensus_states.keys().copied().collect()
+    }
+
+    /// Verifies a header of the primary source and checks it against the
+    /// headers trusted so far. If it conflicts with none of them, it is
+    /// trusted from then on.
+    pub fn add_primary_header(
+        &mut self,
+        header: NearHeader,
+    ) -> Result<Option<DetectedMisbehaviour>, ClientError> {
+        let bps = self.verify(&header)?;
+        let detected = self.detect(&header)?;
+        if detected.is_none() {
+            self.trusted_consensus_states
+                .entry(header.height())
+                .or_insert_with(|| ConsensusStateType::new(bps, header));
+        }
+        Ok(detected)
+    }
+
+    /// Verifies a header of a witness source and checks it against the headers
+    /// of the primary source. Witness headers are never trusted.
+    pub fn check_witness_header(
+        &self,
+        header: &NearHeader,
+    ) -> Result<Option<DetectedMisbehaviour>, ClientError> {
+        self.verify(header)?;
+        self.detect(header)
+    }
+
+    /// Verifies a header of a witness source as [`Self::check_witness_header`],
+    /// along with `ancestry_proof`, the parent of the header or a block proven
+    /// by its `block_merkle_root`, as provided by the witness.
+    ///
+    /// If the block of the proof differs from the trusted header at its
+    /// height, the header and the trusted one are on different forks, and the
+    /// returned misbehaviour holds the proof.
+    pub fn check_witness_header_with_ancestry_proof(
+        &self,
+        header: &NearHeader,
+        ancestry_proof: AncestryProof,
+    ) -> Result<Option<DetectedMisbehaviour>, ClientError> {
+        self.verify(header)?;
+        let block_height = ancestry_proof.block().inner_lite.height;
+        let forked_header = self
+            .trusted_consensus_states
+            .values()
+            .map(|cs| &cs.header)
+            .find(|trusted_header| {
+                trusted_header.light_client_block.inner_lite.height == block_height
+                    && ancestry_proof.proves_fork(trusted_header, header)
+            });
+        if let Some(trusted_header) = forked_header {
+            let misbehaviour = NearMisbehaviour::new(
+                self.client_id.clone(),
+                trusted_header.clone(),
+                header.clone(),
+            )
+            .with_ancestry_proof(ancestry_proof);
+            if let Some(kind) = misbehaviour.kind() {
+                return Ok(Some(DetectedMisbehaviour { kind, misbehaviour }));
+            }
+        }
+        self.detect(header)
+    }
+
+    /// Verifies `header` against the highest trusted consensus state below its
+    /// height, and returns the block producers of the epoch of the header.
+    fn verify(&self, header: &NearHeader) -> Result<Option<Vec<ValidatorStakeView>>, ClientError> {
+        let (_, trusted_consensus_state) = self
+            .trusted_consensus_states
+            .range(..header.height())
+            .next_back()
+            .ok_or_else(|| ClientError::Other {
+                description: format!(
+                    "No trusted consensus state below height {}.",
+                    header.height()
+                ),
+            })?;
+        self.client_state
+            .verify_header_with_trusted_state(trusted_consensus_state, header)?;
+        Ok(trusted_consensus_state.get_block_producers_of(&header.epoch_id()))
+    }
+
+    /// Checks `header` against the trusted headers at the same height and
+    /// right below and above it.
+    fn detect(&self, header: &NearHeader) -> Result<Option<DetectedMisbehaviour>, ClientError> {
+        let height = header.height();
+        let candidates = [
+            self.trusted_consensus_states.get(&height),
+            self.trusted_consensus_states
+                .range(..height)
+                .next_back()
+                .map(|(_, cs)| cs),
+            self.trusted_consensus_states
+                .range(height.increment()..)
+                .next()
+                .map(|(_, cs)| cs),
+        ];
+
+        for trusted_header in candidates.into_iter().flatten().map(|cs| &cs.header) {
+            let misbehaviour = NearMisbehaviour::new(
+                self.client_id.clone(),
+                trusted_header.clone(),
+                header.clone(),
+            );
+            if let Some(kind) = misbehaviour.kind() {
+                return Ok(Some(DetectedMisbehaviour { kind, misbehaviour }));
+            }
+        }
+
+        Ok(None)
+    }
+}
@@ -1,3 +1,6 @@
 pub mod client_state;
 pub mod consensus_state;
 pub mod context;
+#[cfg(feature = "std")]
+pub mod detector;
+pub mod storage_key;
@@ -0,0 +1,82 @@
+//! Mapping of ICS-24 paths to the storage of the IBC contract on NEAR.
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use ibc_core::host::types::path::Path;
+use ics12_near_types::v1::client_state::StorageKeyScheme;
+
+/// Describes how the IBC contract on NEAR stores the commitment at an ICS-24
+/// path: the key of its contract storage, and the encoding of the value.
+///
+/// The returned keys are keys of the contract storage, which the client state
+/// turns into trie keys with the account id of the IBC contract.
+pub trait StorageKeyEncoding {
+    /// Returns the key of the contract storage holding the commitment at
+    /// `path`, with `prefix` being the commitment prefix.
+    fn storage_key(&self, prefix: &[u8], path: &Path) -> Vec<u8>;
+
+    /// Returns the value stored by the contract for the commitment `value` at
+    /// `path`.
+    fn encode_value(&self, path: &Path, value: &[u8]) -> Vec<u8>;
+}
+
+/// Commitments stored under the prefix followed by the path as a string, with
+/// their raw value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PlainStringKeys;
+
+impl StorageKeyEncoding for PlainStringKeys {
+    fn storage_key(&self, prefix: &[u8], path: &Path) -> Vec<u8> {
+        let mut key = prefix.to_vec();
+        key.extend(path.to_string().into_bytes());
+        key
+    }
+
+    fn encode_value(&self, _path: &Path, value: &[u8]) -> Vec<u8> {
+        value.to_vec()
+    }
+}
+
+/// Commitments stored in a near-sdk `LookupMap` keyed by `String` with the
+/// default identity hasher, whose prefix is the commitment prefix.
+///
+/// The key of an entry of the map is its prefix followed by the borsh encoding
+/// of the path string. Its value is the borsh encoding of the commitment, or of
+/// the `u64` of a sequence, i.e. little endian.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct LookupMapBorshKeys;
+
+impl StorageKeyEncoding for LookupMapBorshKeys {
+    fn storage_key(&self, prefix: &[u8], path: &Path) -> Vec<u8> {
+        let mut key = prefix.to_vec();
+        key.extend(borsh::to_vec(&path.to_string()).expect("never failed"));
+        key
+    }
+
+    fn encode_value(&self, path: &Path, value: &[u8]) -> Vec<u8> {
+        // The value of a sequence path is the big endian `u64` stored by
+        // ibc-rs.
+        match (path, <[u8; 8]>::try_from(value)) {
+            (Path::SeqSend(_) | Path::SeqRecv(_) | Path::SeqAck(_), Ok(sequence)) => {
+                borsh::to_vec(&u64::from_be_bytes(sequence)).expect("never failed")
+            }
+            _ => borsh::to_vec(value).expect("never failed"),
+        }
+    }
+}
+
+impl StorageKeyEncoding for StorageKeyScheme {
+    fn storage_key(&self, prefix: &[u8], path: &Path) -> Vec<u8> {
+        match self {
+            Self::PlainString => PlainStringKeys.storage_key(prefix, path),
+            Self::LookupMapBorsh => LookupMapBorshKeys.storage_key(prefix, path),
+        }
+    }
+
+    fn encode_value(&self, path: &Path, value: &[u8]) -> Vec<u8> {
+        match self {
+            Self::PlainString => PlainStringKeys.encode_value(path, value),
+            Self::LookupMapBorsh => LookupMapBorshKeys.encode_value(path, value),
+        }
+    }
+}
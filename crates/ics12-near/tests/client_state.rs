@@ -0,0 +1,53 @@
+//! Queries of the state of the client against the consensus states of its
+//! host.
+
+mod common;
+
+use common::{client_id, MockContext, BLOCK_TIME};
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+
+const LATEST_HEIGHT: u64 = 10;
+/// Epoch duration of the client, in blocks of the headers of the tests.
+const EPOCH_BLOCKS: u64 = 100;
+
+fn client_state() -> ClientState {
+    ClientStateType::new_without_validation(
+        Duration::from_secs(14 * 24 * 60 * 60),
+        Height::new(0, LATEST_HEIGHT).unwrap(),
+        0,
+    )
+    .with_epoch_duration(Duration::from_nanos(EPOCH_BLOCKS * BLOCK_TIME))
+    .into()
+}
+
+fn ctx(host_height: u64) -> MockContext {
+    MockContext::new(host_height).with_consensus_state(LATEST_HEIGHT, CryptoHash([0; 32]))
+}
+
+#[test]
+fn epoch_is_stale_after_two_epoch_durations_since_the_latest_consensus_state() {
+    let client_state = client_state();
+    let stale_height = LATEST_HEIGHT + 2 * EPOCH_BLOCKS;
+
+    assert!(!client_state
+        .is_epoch_stale(&ctx(LATEST_HEIGHT), &client_id())
+        .unwrap());
+    assert!(!client_state
+        .is_epoch_stale(&ctx(stale_height), &client_id())
+        .unwrap());
+    assert!(client_state
+        .is_epoch_stale(&ctx(stale_height + 1), &client_id())
+        .unwrap());
+}
+
+#[test]
+fn epoch_staleness_requires_the_latest_consensus_state() {
+    let ctx = MockContext::new(LATEST_HEIGHT + 2 * EPOCH_BLOCKS + 1)
+        .with_consensus_state(LATEST_HEIGHT - 1, CryptoHash([0; 32]));
+
+    assert!(client_state().is_epoch_stale(&ctx, &client_id()).is_err());
+}
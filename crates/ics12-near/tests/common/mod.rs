@@ -0,0 +1,204 @@
+//! A host of the client for the integration tests, holding consensus states
+//! built from the state roots of in-memory tries, and headers approved by
+//! block producers with deterministic keys.
+
+#![allow(dead_code)]
+
+use core::convert::Infallible;
+use ed25519_dalek::{Keypair, PublicKey as DalekPublicKey, SecretKey, Signer};
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::handler::types::error::ContextError;
+use ibc_core::host::types::identifiers::ClientId;
+use ibc_core::host::types::path::ClientConsensusStatePath;
+use ibc_core::primitives::Timestamp;
+use ics12_near::v1::consensus_state::ConsensusState;
+use ics12_near::v1::context::{CommonContext, ValidationContext};
+use ics12_near_types::v1::consensus_state::ConsensusState as ConsensusStateType;
+use ics12_near_types::v1::header::Header;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::merkle::merklize;
+use ics12_near_types::v1::near_types::signature::{ED25519PublicKey, PublicKey, Signature};
+use ics12_near_types::v1::near_types::{
+    BlockHeaderInnerLite, EpochId, LightClientBlock, ValidatorStakeView, ValidatorStakeViewV1,
+};
+use std::collections::BTreeMap;
+
+/// Timestamp of the header at height `0`, in nanoseconds.
+pub const GENESIS_TIMESTAMP: u64 = 1_700_000_000_000_000_000;
+/// Time between the headers of two consecutive heights, in nanoseconds.
+pub const BLOCK_TIME: u64 = 1_000_000_000;
+/// Epoch of the headers returned by [`header`].
+pub const EPOCH: CryptoHash = CryptoHash([1; 32]);
+/// Next epoch of the headers returned by [`header`].
+pub const NEXT_EPOCH: CryptoHash = CryptoHash([2; 32]);
+/// Stake of each block producer returned by [`block_producers`].
+pub const STAKE: u128 = 100;
+
+pub fn client_id() -> ClientId {
+    "12-near-0".parse().unwrap()
+}
+
+/// Returns a header of [`EPOCH`] at `height`, without approvals, whose
+/// chunks have the state root `state_root`.
+pub fn header(height: u64, state_root: CryptoHash) -> Header {
+    Header {
+        light_client_block: LightClientBlock {
+            prev_block_hash: CryptoHash([0; 32]),
+            next_block_inner_hash: CryptoHash([0; 32]),
+            inner_lite: BlockHeaderInnerLite {
+                height,
+                epoch_id: EpochId(EPOCH),
+                next_epoch_id: EpochId(NEXT_EPOCH),
+                prev_state_root: merklize(&[state_root]).0,
+                outcome_root: CryptoHash([0; 32]),
+                timestamp: GENESIS_TIMESTAMP + height * BLOCK_TIME,
+                next_bp_hash: CryptoHash([3; 32]),
+                block_merkle_root: CryptoHash([0; 32]),
+            },
+            inner_rest_hash: CryptoHash([0; 32]),
+            next_bps: None,
+            approvals_after_next: vec![],
+        },
+        prev_state_root_of_chunks: vec![state_root],
+    }
+}
+
+fn keypair(seed: u8) -> Keypair {
+    let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+    let public = DalekPublicKey::from(&secret);
+    Keypair { secret, public }
+}
+
+/// Returns a block producer with [`STAKE`] for each of `seeds`, the seeds of
+/// their keys.
+pub fn block_producers(seeds: &[u8]) -> Vec<ValidatorStakeView> {
+    seeds
+        .iter()
+        .map(|seed| {
+            ValidatorStakeView::V1(ValidatorStakeViewV1 {
+                account_id: format!("bp{seed}.near"),
+                public_key: PublicKey::ED25519(ED25519PublicKey(keypair(*seed).public.to_bytes())),
+                stake: STAKE,
+            })
+        })
+        .collect()
+}
+
+/// Returns `header` approved by the block producers of `seeds` which are also
+/// in `signers`.
+pub fn signed(mut header: Header, seeds: &[u8], signers: &[u8]) -> Header {
+    let approval_message = header.light_client_block.approval_message();
+    header.light_client_block.approvals_after_next = seeds
+        .iter()
+        .map(|seed| {
+            signers.contains(seed).then(|| {
+                Signature::ED25519(keypair(*seed).sign(&approval_message).to_bytes().to_vec())
+            })
+        })
+        .collect();
+    header
+}
+
+/// A host with the consensus states of the client [`client_id`].
+pub struct MockContext {
+    pub host_height: Height,
+    pub host_timestamp: Timestamp,
+    pub consensus_states: BTreeMap<Height, ConsensusState>,
+}
+
+impl MockContext {
+    /// Returns a host at the time of the header at `host_height`, without
+    /// consensus states.
+    pub fn new(host_height: u64) -> Self {
+        Self {
+            host_height: Height::new(0, host_height).unwrap(),
+            host_timestamp: Timestamp::from_nanoseconds(
+                GENESIS_TIMESTAMP + host_height * BLOCK_TIME,
+            )
+            .unwrap(),
+            consensus_states: BTreeMap::new(),
+        }
+    }
+
+    /// Adds the consensus state of the header at `height` with the state root
+    /// `state_root`.
+    pub fn with_consensus_state(self, height: u64, state_root: CryptoHash) -> Self {
+        self.with_trusted_header(None, header(height, state_root))
+    }
+
+    /// Adds the consensus state of `header`, with `current_bps` as the block
+    /// producers of its epoch.
+    pub fn with_trusted_header(
+        mut self,
+        current_bps: Option<Vec<ValidatorStakeView>>,
+        header: Header,
+    ) -> Self {
+        self.consensus_states.insert(
+            header.height(),
+            ConsensusStateType::new(current_bps, header).into(),
+        );
+        self
+    }
+}
+
+impl CommonContext for MockContext {
+    type ConversionError = Infallible;
+    type AnyConsensusState = ConsensusState;
+
+    fn consensus_state(
+        &self,
+        client_cons_state_path: &ClientConsensusStatePath,
+    ) -> Result<Self::AnyConsensusState, ContextError> {
+        self.consensus_states
+            .iter()
+            .find(|(height, _)| {
+                ClientConsensusStatePath::new(
+                    client_id(),
+                    height.revision_number(),
+                    height.revision_height(),
+                ) == *client_cons_state_path
+            })
+            .map(|(_, consensus_state)| consensus_state.clone())
+            .ok_or_else(|| {
+                ClientError::Other {
+                    description: "consensus state not found".to_string(),
+                }
+                .into()
+            })
+    }
+}
+
+impl ValidationContext for MockContext {
+    fn host_height(&self) -> Result<Height, ContextError> {
+        Ok(self.host_height)
+    }
+
+    fn host_timestamp(&self) -> Result<Timestamp, ContextError> {
+        Ok(self.host_timestamp)
+    }
+
+    fn next_consensus_state(
+        &self,
+        _client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Option<Self::AnyConsensusState>, ContextError> {
+        Ok(self
+            .consensus_states
+            .range(height.increment()..)
+            .next()
+            .map(|(_, consensus_state)| consensus_state.clone()))
+    }
+
+    fn prev_consensus_state(
+        &self,
+        _client_id: &ClientId,
+        height: &Height,
+    ) -> Result<Option<Self::AnyConsensusState>, ContextError> {
+        Ok(self
+            .consensus_states
+            .range(..*height)
+            .next_back()
+            .map(|(_, consensus_state)| consensus_state.clone()))
+    }
+}
@@ -0,0 +1,160 @@
+//! Offline detection of misbehaviour in the headers of witness sources, and
+//! its verification by the client.
+
+mod common;
+
+use common::{block_producers, client_id, header, signed, MockContext};
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near::v1::detector::{MisbehaviourDetector, MisbehaviourKind};
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::consensus_state::ConsensusState as ConsensusStateType;
+use ics12_near_types::v1::header::Header;
+use ics12_near_types::v1::misbehaviour::{AncestryProof, Misbehaviour};
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::LightClientBlockLite;
+
+const BLOCK_PRODUCERS: [u8; 4] = [1, 2, 3, 4];
+const TRUSTED_HEIGHT: u64 = 10;
+
+fn client_state() -> ClientState {
+    ClientStateType::new_without_validation(
+        Duration::from_secs(60 * 60),
+        Height::new(0, TRUSTED_HEIGHT).unwrap(),
+        0,
+    )
+    .into()
+}
+
+fn trusted_header() -> Header {
+    header(TRUSTED_HEIGHT, CryptoHash([0; 32]))
+}
+
+/// Returns the header at `height` of the chain with the state root
+/// `state_root`, approved by `signers`.
+fn approved_header(height: u64, state_root: u8, signers: &[u8]) -> Header {
+    signed(
+        header(height, CryptoHash([state_root; 32])),
+        &BLOCK_PRODUCERS,
+        signers,
+    )
+}
+
+/// Returns a detector which verified the header of the primary source at
+/// height 20.
+fn detector() -> MisbehaviourDetector {
+    let mut detector = MisbehaviourDetector::new(
+        client_id(),
+        client_state(),
+        ConsensusStateType::new(Some(block_producers(&BLOCK_PRODUCERS)), trusted_header()),
+    );
+    assert_eq!(
+        detector
+            .add_primary_header(approved_header(20, 1, &[1, 2, 3]))
+            .unwrap(),
+        None
+    );
+    detector
+}
+
+fn lite(header: &Header) -> LightClientBlockLite {
+    let block = &header.light_client_block;
+    LightClientBlockLite {
+        inner_lite: block.inner_lite.clone(),
+        inner_rest_hash: block.inner_rest_hash,
+        prev_block_hash: block.prev_block_hash,
+    }
+}
+
+#[test]
+fn primary_headers_are_trusted() {
+    let detector = detector();
+    assert_eq!(
+        detector.trusted_heights(),
+        [
+            Height::new(0, TRUSTED_HEIGHT).unwrap(),
+            Height::new(0, 20).unwrap()
+        ]
+    );
+    assert_eq!(
+        detector
+            .check_witness_header(&approved_header(20, 1, &[2, 3, 4]))
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn witness_header_without_enough_approvals_is_rejected() {
+    assert!(detector()
+        .check_witness_header(&approved_header(20, 2, &[3, 4]))
+        .is_err());
+}
+
+#[test]
+fn conflicting_block_is_detected_and_verified_by_the_client() {
+    let detected = detector()
+        .check_witness_header(&approved_header(20, 2, &[2, 3, 4]))
+        .unwrap()
+        .unwrap();
+    assert_eq!(detected.kind, MisbehaviourKind::ConflictingBlocks);
+    assert_eq!(
+        Misbehaviour::try_from(detected.to_any()).unwrap(),
+        detected.misbehaviour
+    );
+
+    let ctx = MockContext::new(30)
+        .with_trusted_header(Some(block_producers(&BLOCK_PRODUCERS)), trusted_header());
+    let client_state = client_state();
+    client_state
+        .verify_misbehaviour(&ctx, &client_id(), detected.misbehaviour.clone())
+        .unwrap();
+    assert!(client_state
+        .check_for_misbehaviour_misbehaviour(&detected.misbehaviour)
+        .unwrap());
+}
+
+#[test]
+fn conflicting_timestamp_is_detected() {
+    let mut earlier = header(30, CryptoHash([2; 32]));
+    earlier.light_client_block.inner_lite.timestamp = approved_header(20, 1, &[])
+        .light_client_block
+        .inner_lite
+        .timestamp;
+    let earlier = signed(earlier, &BLOCK_PRODUCERS, &[2, 3, 4]);
+
+    let detected = detector().check_witness_header(&earlier).unwrap().unwrap();
+    assert_eq!(detected.kind, MisbehaviourKind::ConflictingTimestamps);
+}
+
+#[test]
+fn fork_is_detected_with_an_ancestry_proof() {
+    // The witness follows another block at height 20.
+    let other = approved_header(20, 2, &[2, 3, 4]);
+    let mut child = header(21, CryptoHash([2; 32]));
+    child.light_client_block.prev_block_hash = other.light_client_block.current_block_hash();
+    let child = signed(child, &BLOCK_PRODUCERS, &[2, 3, 4]);
+
+    let detector = detector();
+    assert_eq!(detector.check_witness_header(&child).unwrap(), None);
+
+    let detected = detector
+        .check_witness_header_with_ancestry_proof(&child, AncestryProof::PrevBlock(lite(&other)))
+        .unwrap()
+        .unwrap();
+    assert_eq!(detected.kind, MisbehaviourKind::Fork);
+    assert!(detected.misbehaviour.ancestry_proof().is_some());
+
+    // The primary block at height 20 proves nothing.
+    let primary = approved_header(20, 1, &[]);
+    assert_eq!(
+        detector
+            .check_witness_header_with_ancestry_proof(
+                &child,
+                AncestryProof::PrevBlock(lite(&primary))
+            )
+            .unwrap(),
+        None
+    );
+}
@@ -0,0 +1,273 @@
+//! Verification of the execution outcomes of transactions and receipts, and of
+//! the NEP-297 events they log, against the block merkle root of the trusted
+//! consensus states of the client.
+
+mod common;
+
+use common::{client_id, header, MockContext};
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::near_types::hash::{combine_hash, CryptoHash};
+use ics12_near_types::v1::near_types::light_client_proof::{
+    ExecutionOutcomeProof, ExecutionOutcomeWithIdAndProof,
+};
+use ics12_near_types::v1::near_types::merkle::{merklize, Direction, MerklePathItem};
+use ics12_near_types::v1::near_types::transaction::{
+    ExecutionOutcome, ExecutionOutcomeWithId, ExecutionStatus,
+};
+use ics12_near_types::v1::near_types::LightClientBlockLite;
+
+const HEIGHT: u64 = 100;
+const TRUSTING_PERIOD: Duration = Duration::from_secs(60 * 60);
+const EXECUTOR: &str = "app.near";
+
+fn client_state() -> ClientState {
+    ClientStateType::new_without_validation(TRUSTING_PERIOD, Height::new(0, HEIGHT).unwrap(), 0)
+        .into()
+}
+
+fn height() -> Height {
+    Height::new(0, HEIGHT).unwrap()
+}
+
+fn outcome(id: u8, executor_id: &str, logs: &[&str]) -> ExecutionOutcomeWithId {
+    ExecutionOutcomeWithId {
+        id: CryptoHash([id; 32]),
+        outcome: ExecutionOutcome {
+            logs: logs.iter().map(|log| log.to_string()).collect(),
+            receipt_ids: vec![CryptoHash([id + 1; 32])],
+            gas_burnt: 2_428_000_000_000,
+            tokens_burnt: 242_800_000_000_000_000_000,
+            executor_id: executor_id.to_string(),
+            status: ExecutionStatus::SuccessValue(vec![]),
+        },
+    }
+}
+
+/// Returns the proof of `outcome`, executed in a shard along with another
+/// outcome, in a block 10 blocks below [`HEIGHT`], along with the block
+/// merkle root of a later block including the block of the outcome.
+fn prove(outcome: ExecutionOutcomeWithId) -> (ExecutionOutcomeProof, CryptoHash) {
+    let other_outcome = outcome_with_other_id(&outcome);
+    let (shard_outcome_root, outcome_paths) =
+        merklize(&[outcome.to_hashes(), other_outcome.to_hashes()]);
+    let (outcome_root, shard_paths) = merklize(&[shard_outcome_root, CryptoHash([7; 32])]);
+
+    let mut block = header(HEIGHT - 10, CryptoHash([0; 32])).light_client_block;
+    block.inner_lite.outcome_root = outcome_root;
+    let block_header_lite = LightClientBlockLite {
+        inner_lite: block.inner_lite,
+        inner_rest_hash: block.inner_rest_hash,
+        prev_block_hash: block.prev_block_hash,
+    };
+    let block_hash = block_header_lite.current_block_hash();
+    let other_block_hash = CryptoHash([8; 32]);
+
+    let proof = ExecutionOutcomeProof {
+        outcome_proof: ExecutionOutcomeWithIdAndProof {
+            proof: outcome_paths[0].clone(),
+            block_hash,
+            outcome_with_id: outcome,
+        },
+        outcome_root_proof: shard_paths[0].clone(),
+        block_header_lite,
+        block_proof: vec![MerklePathItem {
+            hash: other_block_hash,
+            direction: Direction::Right,
+        }],
+    };
+    (proof, combine_hash(&block_hash, &other_block_hash))
+}
+
+fn outcome_with_other_id(outcome: &ExecutionOutcomeWithId) -> ExecutionOutcomeWithId {
+    ExecutionOutcomeWithId {
+        id: CryptoHash([0xff; 32]),
+        outcome: outcome.outcome.clone(),
+    }
+}
+
+/// Returns a host, at the time of the header at `host_height`, trusting a
+/// header at [`HEIGHT`] with `block_merkle_root`.
+fn ctx(host_height: u64, block_merkle_root: CryptoHash) -> MockContext {
+    let mut trusted_header = header(HEIGHT, CryptoHash([0; 32]));
+    trusted_header
+        .light_client_block
+        .inner_lite
+        .block_merkle_root = block_merkle_root;
+    MockContext::new(host_height).with_trusted_header(None, trusted_header)
+}
+
+#[test]
+fn execution_outcome_is_verified() {
+    let (proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &[]));
+
+    client_state()
+        .verify_execution_outcome(
+            &ctx(HEIGHT, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+        )
+        .unwrap();
+}
+
+#[test]
+fn tampered_execution_outcome_is_rejected() {
+    let (proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &[]));
+    let ctx = ctx(HEIGHT, block_merkle_root);
+
+    let mut tampered = proof.clone();
+    tampered.outcome_proof.outcome_with_id.outcome.status =
+        ExecutionStatus::SuccessValue(b"1".to_vec());
+    assert!(client_state()
+        .verify_execution_outcome(&ctx, &client_id(), &height(), &tampered)
+        .is_err());
+
+    let mut tampered = proof.clone();
+    tampered.outcome_proof.outcome_with_id.id = CryptoHash([2; 32]);
+    assert!(client_state()
+        .verify_execution_outcome(&ctx, &client_id(), &height(), &tampered)
+        .is_err());
+
+    // The block of the outcome must be the one included in the trusted block.
+    let mut tampered = proof;
+    tampered.outcome_proof.block_hash = CryptoHash([9; 32]);
+    assert!(client_state()
+        .verify_execution_outcome(&ctx, &client_id(), &height(), &tampered)
+        .is_err());
+}
+
+#[test]
+fn execution_outcome_of_untrusted_block_is_rejected() {
+    let (proof, _) = prove(outcome(1, EXECUTOR, &[]));
+
+    assert!(client_state()
+        .verify_execution_outcome(
+            &ctx(HEIGHT, CryptoHash([9; 32])),
+            &client_id(),
+            &height(),
+            &proof,
+        )
+        .is_err());
+}
+
+#[test]
+fn execution_outcome_is_rejected_after_the_trusting_period() {
+    let (proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &[]));
+    let trusting_period_end = HEIGHT + TRUSTING_PERIOD.as_secs();
+
+    client_state()
+        .verify_execution_outcome(
+            &ctx(trusting_period_end, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+        )
+        .unwrap();
+    assert!(client_state()
+        .verify_execution_outcome(
+            &ctx(trusting_period_end + 1, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+        )
+        .is_err());
+}
+
+#[test]
+fn execution_outcome_is_rejected_by_frozen_client() {
+    let (proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &[]));
+    let client_state: ClientState =
+        ClientStateType::new_without_validation(TRUSTING_PERIOD, height(), 0)
+            .with_frozen_height(Height::new(0, 1).unwrap())
+            .into();
+
+    assert!(client_state
+        .verify_execution_outcome(
+            &ctx(HEIGHT, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+        )
+        .is_err());
+}
+
+const NFT_MINT: &str = r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[{"owner_id":"alice.near","token_ids":["1"]}]}"#;
+
+#[test]
+fn event_log_is_verified() {
+    let (proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &["minting", NFT_MINT]));
+
+    let event = client_state()
+        .verify_event_log(
+            &ctx(HEIGHT, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+            &EXECUTOR.to_string(),
+            1,
+        )
+        .unwrap();
+    assert_eq!(event.standard, "nep171");
+    assert_eq!(event.version, "1.0.0");
+    assert_eq!(event.event, "nft_mint");
+    assert_eq!(
+        Some(event.json.as_str()),
+        NFT_MINT.strip_prefix("EVENT_JSON:")
+    );
+}
+
+#[test]
+fn event_log_of_other_executor_is_rejected() {
+    let (proof, block_merkle_root) = prove(outcome(1, "impostor.near", &[NFT_MINT]));
+
+    assert!(client_state()
+        .verify_event_log(
+            &ctx(HEIGHT, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+            &EXECUTOR.to_string(),
+            0,
+        )
+        .is_err());
+}
+
+#[test]
+fn tampered_event_log_is_rejected() {
+    let (mut proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &[NFT_MINT]));
+    proof.outcome_proof.outcome_with_id.outcome.logs[0] = NFT_MINT.replace("alice", "mallory");
+
+    assert!(client_state()
+        .verify_event_log(
+            &ctx(HEIGHT, block_merkle_root),
+            &client_id(),
+            &height(),
+            &proof,
+            &EXECUTOR.to_string(),
+            0,
+        )
+        .is_err());
+}
+
+#[test]
+fn missing_or_non_event_log_is_rejected() {
+    let (proof, block_merkle_root) = prove(outcome(1, EXECUTOR, &["minting", NFT_MINT]));
+    let ctx = ctx(HEIGHT, block_merkle_root);
+
+    // The first log is not an event, and there is no third log.
+    for log_index in [0, 2] {
+        assert!(client_state()
+            .verify_event_log(
+                &ctx,
+                &client_id(),
+                &height(),
+                &proof,
+                &EXECUTOR.to_string(),
+                log_index,
+            )
+            .is_err());
+    }
+}
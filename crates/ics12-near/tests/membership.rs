@@ -0,0 +1,283 @@
+//! Verification of commitment proofs built from an in-memory trie of the
+//! state of the IBC contract, with the settings of the client state.
+
+use core::time::Duration;
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::error::ClientError;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::{
+    CommitmentPrefix, CommitmentProofBytes, CommitmentRoot,
+};
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::{CommitmentPath, Path};
+use ibc_proto::google::protobuf::Any;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::error::Error;
+use ics12_near_types::v1::near_types::account::{account_key, Account};
+use ics12_near_types::v1::near_types::get_raw_prefix_for_contract_data;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::trie::{builder::InMemoryTrie, ProofLimits};
+use ics12_near_types::v1::state_proof::NearStateProof;
+
+const IBC_CONTRACT: &str = "ibc.testnet";
+const SHARD_ID: u64 = 0;
+const CODE_HASH: CryptoHash = CryptoHash([5; 32]);
+
+fn client_state_type() -> ClientStateType {
+    ClientStateType::new_without_validation(
+        Duration::from_secs(14 * 24 * 60 * 60),
+        Height::new(0, 100).unwrap(),
+        0,
+    )
+    .with_ibc_contract_account_id(IBC_CONTRACT.to_string())
+}
+
+fn prefix() -> CommitmentPrefix {
+    CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap()
+}
+
+fn commitment_path(sequence: u64) -> Path {
+    Path::Commitment(CommitmentPath {
+        port_id: PortId::transfer(),
+        channel_id: ChannelId::new(0),
+        sequence: Sequence::from(sequence),
+    })
+}
+
+/// Returns the trie key of the commitment at `path`.
+fn trie_key(path: &Path) -> Vec<u8> {
+    let mut key = b"ibc".to_vec();
+    key.extend(path.to_string().into_bytes());
+    get_raw_prefix_for_contract_data(&IBC_CONTRACT.to_string(), &key)
+}
+
+/// Returns a trie with the packet commitments of sequences 1 to 4.
+fn trie() -> InMemoryTrie {
+    (1..=4)
+        .map(|sequence| {
+            (
+                trie_key(&commitment_path(sequence)),
+                vec![sequence as u8; 32],
+            )
+        })
+        .collect()
+}
+
+/// Returns the trie of [`trie`], along with the account of the IBC contract,
+/// whose code has [`CODE_HASH`].
+fn trie_with_account() -> (InMemoryTrie, Account) {
+    let account = Account {
+        amount: 0,
+        locked: 0,
+        code_hash: CODE_HASH,
+        storage_usage: 0,
+    };
+    let mut trie = trie();
+    trie.insert(
+        account_key(&IBC_CONTRACT.to_string()),
+        borsh::to_vec(&account).unwrap(),
+    );
+    (trie, account)
+}
+
+fn root(trie: &InMemoryTrie) -> CommitmentRoot {
+    CommitmentRoot::from_bytes(&borsh::to_vec(&vec![trie.root()]).unwrap())
+}
+
+fn proof(trie: &InMemoryTrie, path: &Path) -> (usize, CommitmentProofBytes) {
+    let nodes: Vec<Vec<u8>> = trie
+        .prove(&trie_key(path))
+        .iter()
+        .map(|node| node.encode())
+        .collect();
+    (nodes.len(), NearStateProof::new(SHARD_ID, nodes).into())
+}
+
+/// Returns the proof of `path` along with the account of the IBC contract.
+fn proof_with_account(trie: &InMemoryTrie, account: &Account, path: &Path) -> CommitmentProofBytes {
+    let encode =
+        |key: &[u8]| -> Vec<Vec<u8>> { trie.prove(key).iter().map(|node| node.encode()).collect() };
+    NearStateProof::new(SHARD_ID, encode(&trie_key(path)))
+        .with_account_proof(
+            borsh::to_vec(account).unwrap(),
+            encode(&account_key(&IBC_CONTRACT.to_string())),
+        )
+        .into()
+}
+
+#[test]
+fn proof_within_configured_max_nodes_verifies() {
+    let trie = trie();
+    let path = commitment_path(2);
+    let (node_count, proof) = proof(&trie, &path);
+    assert!(node_count > 2);
+
+    for proof_limits in [
+        ProofLimits::default(),
+        ProofLimits {
+            max_nodes: node_count,
+            ..ProofLimits::default()
+        },
+    ] {
+        let client_state: ClientState = client_state_type().with_proof_limits(proof_limits).into();
+        client_state
+            .verify_membership(&prefix(), &proof, &root(&trie), path.clone(), vec![2; 32])
+            .unwrap();
+    }
+}
+
+#[test]
+fn proof_over_configured_max_nodes_is_rejected() {
+    let trie = trie();
+    let path = commitment_path(2);
+    let (node_count, proof) = proof(&trie, &path);
+
+    let client_state: ClientState = client_state_type()
+        .with_proof_limits(ProofLimits {
+            max_nodes: node_count - 1,
+            ..ProofLimits::default()
+        })
+        .into();
+    assert!(client_state
+        .verify_membership(&prefix(), &proof, &root(&trie), path, vec![2; 32])
+        .is_err());
+    // The proof is valid within the default limits.
+    ClientState::from(client_state_type())
+        .verify_membership(
+            &prefix(),
+            &proof,
+            &root(&trie),
+            commitment_path(2),
+            vec![2; 32],
+        )
+        .unwrap();
+}
+
+#[test]
+fn absence_proof_over_configured_max_nodes_is_rejected() {
+    let trie = trie();
+    let path = commitment_path(5);
+    let (node_count, proof) = proof(&trie, &path);
+
+    let client_state: ClientState = client_state_type()
+        .with_proof_limits(ProofLimits {
+            max_nodes: node_count,
+            ..ProofLimits::default()
+        })
+        .into();
+    client_state
+        .verify_non_membership(&prefix(), &proof, &root(&trie), path.clone())
+        .unwrap();
+
+    let client_state: ClientState = client_state_type()
+        .with_proof_limits(ProofLimits {
+            max_nodes: node_count - 1,
+            ..ProofLimits::default()
+        })
+        .into();
+    assert!(client_state
+        .verify_non_membership(&prefix(), &proof, &root(&trie), path)
+        .is_err());
+}
+
+#[test]
+fn pinned_client_state_round_trips() {
+    let client_state: ClientState = client_state_type()
+        .with_ibc_contract_code_hash(CODE_HASH)
+        .into();
+
+    let any = Any::from(client_state.clone());
+    assert_eq!(ClientState::try_from(any).unwrap(), client_state);
+}
+
+#[test]
+fn pinned_code_hash_is_verified() {
+    let (trie, account) = trie_with_account();
+    let client_state: ClientState = client_state_type()
+        .with_ibc_contract_code_hash(CODE_HASH)
+        .into();
+
+    let path = commitment_path(2);
+    let proof = proof_with_account(&trie, &account, &path);
+    client_state
+        .verify_membership(&prefix(), &proof, &root(&trie), path, vec![2; 32])
+        .unwrap();
+    let path = commitment_path(5);
+    let proof = proof_with_account(&trie, &account, &path);
+    client_state
+        .verify_non_membership(&prefix(), &proof, &root(&trie), path)
+        .unwrap();
+
+    // Without the account, the code hash can not be checked.
+    let path = commitment_path(2);
+    let (_, proof) = proof(&trie, &path);
+    assert!(client_state
+        .verify_membership(&prefix(), &proof, &root(&trie), path, vec![2; 32])
+        .is_err());
+}
+
+#[test]
+fn mismatched_code_hash_is_rejected() {
+    let (trie, account) = trie_with_account();
+    let expected = CryptoHash([6; 32]);
+    let client_state: ClientState = client_state_type()
+        .with_ibc_contract_code_hash(expected)
+        .into();
+
+    let path = commitment_path(2);
+    let proof = proof_with_account(&trie, &account, &path);
+    let err = client_state
+        .verify_membership(&prefix(), &proof, &root(&trie), path, vec![2; 32])
+        .unwrap_err();
+    let mismatch = Error::MismatchContractCodeHash {
+        expected,
+        actual: CODE_HASH,
+    };
+    assert!(
+        matches!(&err, ClientError::ClientSpecific { description } if *description == mismatch.to_string()),
+        "{err:?}"
+    );
+}
+
+#[test]
+fn legacy_proof_verifies() {
+    let trie = trie();
+    let path = commitment_path(2);
+    let nodes: Vec<Vec<u8>> = trie
+        .prove(&trie_key(&path))
+        .iter()
+        .map(|node| node.encode())
+        .collect();
+    let proof = CommitmentProofBytes::try_from(borsh::to_vec(&nodes).unwrap()).unwrap();
+
+    let client_state: ClientState = client_state_type().into();
+    client_state
+        .verify_membership(&prefix(), &proof, &root(&trie), path.clone(), vec![2; 32])
+        .unwrap();
+    assert!(client_state
+        .verify_membership(&prefix(), &proof, &root(&trie), path, vec![3; 32])
+        .is_err());
+}
+
+#[test]
+fn proof_with_unused_trailing_node_is_rejected() {
+    let trie = trie();
+    let path = commitment_path(2);
+    let mut nodes: Vec<Vec<u8>> = trie
+        .prove(&trie_key(&path))
+        .iter()
+        .map(|node| node.encode())
+        .collect();
+    nodes.push(
+        trie.prove(&trie_key(&commitment_path(4)))
+            .pop()
+            .unwrap()
+            .encode(),
+    );
+    let proof: CommitmentProofBytes = NearStateProof::new(SHARD_ID, nodes).into();
+
+    assert!(ClientState::from(client_state_type())
+        .verify_membership(&prefix(), &proof, &root(&trie), path, vec![2; 32])
+        .is_err());
+}
@@ -0,0 +1,185 @@
+//! Verification of misbehaviour against the trusted consensus states of the
+//! client, and extraction of the block producers which equivocated.
+
+mod common;
+
+use common::{block_producers, client_id, header, signed, MockContext, STAKE};
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::header::Header;
+use ics12_near_types::v1::misbehaviour::Misbehaviour;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+
+const BLOCK_PRODUCERS: [u8; 4] = [1, 2, 3, 4];
+const TRUSTED_HEIGHT: u64 = 10;
+const HEIGHT: u64 = 20;
+const TRUSTING_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+fn client_state() -> ClientState {
+    ClientStateType::new_without_validation(
+        TRUSTING_PERIOD,
+        Height::new(0, TRUSTED_HEIGHT).unwrap(),
+        0,
+    )
+    .into()
+}
+
+/// Returns a host trusting the header at [`TRUSTED_HEIGHT`], with the block
+/// producers of its epoch, at the time of the header at `host_height`.
+fn ctx(host_height: u64) -> MockContext {
+    MockContext::new(host_height).with_trusted_header(
+        Some(block_producers(&BLOCK_PRODUCERS)),
+        header(TRUSTED_HEIGHT, CryptoHash([0; 32])),
+    )
+}
+
+/// Returns a header at [`HEIGHT`] with the state root `state_root`, approved
+/// by `signers`.
+fn conflicting_header(state_root: u8, signers: &[u8]) -> Header {
+    signed(
+        header(HEIGHT, CryptoHash([state_root; 32])),
+        &BLOCK_PRODUCERS,
+        signers,
+    )
+}
+
+fn misbehaviour(header1: Header, header2: Header) -> Misbehaviour {
+    Misbehaviour::new(client_id(), header1, header2)
+}
+
+#[test]
+fn conflicting_headers_are_verified_against_trusted_consensus_states() {
+    let misbehaviour = misbehaviour(
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[2, 3, 4]),
+    );
+
+    client_state()
+        .verify_misbehaviour(&ctx(HEIGHT), &client_id(), misbehaviour.clone())
+        .unwrap();
+    assert!(client_state()
+        .check_for_misbehaviour_misbehaviour(&misbehaviour)
+        .unwrap());
+}
+
+#[test]
+fn header_without_enough_approvals_is_rejected() {
+    // Half of the stake does not make more than two thirds of it.
+    let misbehaviour = misbehaviour(
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[3, 4]),
+    );
+
+    assert!(client_state()
+        .verify_misbehaviour(&ctx(HEIGHT), &client_id(), misbehaviour)
+        .is_err());
+}
+
+#[test]
+fn header_with_forged_approval_is_rejected() {
+    let mut forged = conflicting_header(2, &[2, 3, 4]);
+    let approvals = &mut forged.light_client_block.approvals_after_next;
+    approvals[1] = approvals[2].clone();
+    let misbehaviour = misbehaviour(conflicting_header(1, &[1, 2, 3]), forged);
+
+    assert!(client_state()
+        .verify_misbehaviour(&ctx(HEIGHT), &client_id(), misbehaviour)
+        .is_err());
+}
+
+#[test]
+fn misbehaviour_without_trusted_consensus_state_below_it_is_rejected() {
+    let ctx = MockContext::new(HEIGHT).with_trusted_header(
+        Some(block_producers(&BLOCK_PRODUCERS)),
+        header(HEIGHT, CryptoHash([0; 32])),
+    );
+    let misbehaviour = misbehaviour(
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[2, 3, 4]),
+    );
+
+    assert!(client_state()
+        .verify_misbehaviour(&ctx, &client_id(), misbehaviour)
+        .is_err());
+}
+
+#[test]
+fn misbehaviour_with_expired_trusted_consensus_state_is_rejected() {
+    let misbehaviour = misbehaviour(
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[2, 3, 4]),
+    );
+    let trusting_period_end = TRUSTED_HEIGHT + TRUSTING_PERIOD.as_secs();
+
+    client_state()
+        .verify_misbehaviour(
+            &ctx(trusting_period_end - 1),
+            &client_id(),
+            misbehaviour.clone(),
+        )
+        .unwrap();
+    assert!(client_state()
+        .verify_misbehaviour(&ctx(trusting_period_end + 1), &client_id(), misbehaviour)
+        .is_err());
+}
+
+#[test]
+fn misbehaviour_of_another_client_is_rejected() {
+    let misbehaviour = Misbehaviour::new(
+        "12-near-1".parse().unwrap(),
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[2, 3, 4]),
+    );
+
+    assert!(client_state()
+        .verify_misbehaviour(&ctx(HEIGHT), &client_id(), misbehaviour)
+        .is_err());
+}
+
+#[test]
+fn equivocation_evidence_holds_the_block_producers_which_approved_both_headers() {
+    let misbehaviour = misbehaviour(
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[2, 3, 4]),
+    );
+
+    let evidence = client_state()
+        .equivocation_evidence(&ctx(HEIGHT), &client_id(), &misbehaviour)
+        .unwrap();
+    let equivocators: Vec<_> = evidence
+        .equivocators
+        .iter()
+        .map(|bp| bp.account_id.as_str())
+        .collect();
+    assert_eq!(equivocators, ["bp2.near", "bp3.near"]);
+    assert_eq!(evidence.equivocating_stake(), 2 * STAKE);
+    assert_eq!(evidence.total_stake, 4 * STAKE);
+}
+
+#[test]
+fn equivocation_evidence_of_headers_at_different_heights_is_rejected() {
+    let higher = signed(
+        header(HEIGHT + 1, CryptoHash([2; 32])),
+        &BLOCK_PRODUCERS,
+        &[2, 3, 4],
+    );
+    let misbehaviour = misbehaviour(conflicting_header(1, &[1, 2, 3]), higher);
+
+    assert!(client_state()
+        .equivocation_evidence(&ctx(HEIGHT + 1), &client_id(), &misbehaviour)
+        .is_err());
+}
+
+#[test]
+fn equivocation_evidence_of_unverified_misbehaviour_is_rejected() {
+    let misbehaviour = misbehaviour(
+        conflicting_header(1, &[1, 2, 3]),
+        conflicting_header(2, &[3, 4]),
+    );
+
+    assert!(client_state()
+        .equivocation_evidence(&ctx(HEIGHT), &client_id(), &misbehaviour)
+        .is_err());
+}
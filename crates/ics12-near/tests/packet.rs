@@ -0,0 +1,277 @@
+//! Verification of the packet commitments, acknowledgements, receipts and
+//! sequences of the IBC contract on NEAR against the consensus states of the
+//! client, with both storage key schemes.
+
+mod common;
+
+use common::{client_id, MockContext};
+use core::time::Duration;
+use ibc_core::channel::types::acknowledgement::Acknowledgement;
+use ibc_core::channel::types::commitment::{compute_ack_commitment, compute_packet_commitment};
+use ibc_core::channel::types::packet::Packet;
+use ibc_core::channel::types::timeout::TimeoutHeight;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::{CommitmentPrefix, CommitmentProofBytes};
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::{AckPath, CommitmentPath, Path, ReceiptPath, SeqRecvPath};
+use ibc_core::primitives::Timestamp;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::{ClientState as ClientStateType, StorageKeyScheme};
+use ics12_near_types::v1::near_types::trie::builder::InMemoryTrie;
+use ics12_near_types::v1::state_proof::NearStateProof;
+
+const IBC_CONTRACT: &str = "ibc.testnet";
+const SHARD_ID: u64 = 0;
+const HEIGHT: u64 = 100;
+
+fn client_state(scheme: StorageKeyScheme) -> ClientState {
+    ClientStateType::new_without_validation(
+        Duration::from_secs(14 * 24 * 60 * 60),
+        Height::new(0, HEIGHT).unwrap(),
+        0,
+    )
+    .with_ibc_contract_account_id(IBC_CONTRACT.to_string())
+    .with_storage_key_scheme(scheme)
+    .into()
+}
+
+fn height() -> Height {
+    Height::new(0, HEIGHT).unwrap()
+}
+
+fn prefix() -> CommitmentPrefix {
+    CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap()
+}
+
+fn packet(sequence: u64, data: &[u8]) -> Packet {
+    Packet {
+        seq_on_a: Sequence::from(sequence),
+        port_id_on_a: PortId::transfer(),
+        chan_id_on_a: ChannelId::new(0),
+        port_id_on_b: PortId::transfer(),
+        chan_id_on_b: ChannelId::new(1),
+        data: data.to_vec(),
+        timeout_height_on_b: TimeoutHeight::Never,
+        timeout_timestamp_on_b: Timestamp::none(),
+    }
+}
+
+fn ack() -> Acknowledgement {
+    Acknowledgement::try_from(b"{\"result\":\"AQ==\"}".to_vec()).unwrap()
+}
+
+fn commitment_path(packet: &Packet) -> Path {
+    Path::Commitment(CommitmentPath {
+        port_id: packet.port_id_on_a.clone(),
+        channel_id: packet.chan_id_on_a.clone(),
+        sequence: packet.seq_on_a,
+    })
+}
+
+fn ack_path(packet: &Packet) -> Path {
+    Path::Ack(AckPath {
+        port_id: packet.port_id_on_b.clone(),
+        channel_id: packet.chan_id_on_b.clone(),
+        sequence: packet.seq_on_a,
+    })
+}
+
+fn receipt_path(packet: &Packet) -> Path {
+    Path::Receipt(ReceiptPath {
+        port_id: packet.port_id_on_b.clone(),
+        channel_id: packet.chan_id_on_b.clone(),
+        sequence: packet.seq_on_a,
+    })
+}
+
+fn seq_recv_path() -> Path {
+    Path::SeqRecv(SeqRecvPath(PortId::transfer(), ChannelId::new(1)))
+}
+
+/// Returns the value the IBC contract stores for `bytes` with `scheme`.
+fn stored_bytes(scheme: StorageKeyScheme, bytes: Vec<u8>) -> Vec<u8> {
+    match scheme {
+        StorageKeyScheme::PlainString => bytes,
+        StorageKeyScheme::LookupMapBorsh => borsh::to_vec(&bytes).unwrap(),
+    }
+}
+
+/// Returns the value the IBC contract stores for `sequence` with `scheme`.
+fn stored_sequence(scheme: StorageKeyScheme, sequence: u64) -> Vec<u8> {
+    match scheme {
+        StorageKeyScheme::PlainString => sequence.to_be_bytes().to_vec(),
+        StorageKeyScheme::LookupMapBorsh => sequence.to_le_bytes().to_vec(),
+    }
+}
+
+/// Returns the state of the IBC contract after sending packet 1 and receiving
+/// and acknowledging packet 2, with the next sequence to receive being 3.
+fn trie(client_state: &ClientState, scheme: StorageKeyScheme) -> InMemoryTrie {
+    let sent = packet(1, b"sent");
+    let received = packet(2, b"received");
+    let commitment = compute_packet_commitment(
+        &sent.data,
+        &sent.timeout_height_on_b,
+        &sent.timeout_timestamp_on_b,
+    );
+    [
+        (
+            commitment_path(&sent),
+            stored_bytes(scheme, commitment.into_vec()),
+        ),
+        (
+            ack_path(&received),
+            stored_bytes(scheme, compute_ack_commitment(&ack()).into_vec()),
+        ),
+        (receipt_path(&received), stored_bytes(scheme, vec![1])),
+        (seq_recv_path(), stored_sequence(scheme, 3)),
+    ]
+    .into_iter()
+    .map(|(path, value)| (client_state.storage_key(&scheme, &prefix(), &path), value))
+    .collect()
+}
+
+fn proof(
+    client_state: &ClientState,
+    scheme: StorageKeyScheme,
+    trie: &InMemoryTrie,
+    path: &Path,
+) -> CommitmentProofBytes {
+    let key = client_state.storage_key(&scheme, &prefix(), path);
+    let nodes = trie.prove(&key).iter().map(|node| node.encode()).collect();
+    NearStateProof::new(SHARD_ID, nodes).into()
+}
+
+/// Returns the client state with `scheme`, the state of the IBC contract and
+/// a host with its consensus state at [`HEIGHT`].
+fn setup(scheme: StorageKeyScheme) -> (ClientState, InMemoryTrie, MockContext) {
+    let client_state = client_state(scheme);
+    let trie = trie(&client_state, scheme);
+    let ctx = MockContext::new(HEIGHT + 10).with_consensus_state(HEIGHT, trie.root());
+    (client_state, trie, ctx)
+}
+
+const SCHEMES: [StorageKeyScheme; 2] = [
+    StorageKeyScheme::PlainString,
+    StorageKeyScheme::LookupMapBorsh,
+];
+
+#[test]
+fn packet_commitment_is_verified() {
+    for scheme in SCHEMES {
+        let (client_state, trie, ctx) = setup(scheme);
+        let sent = packet(1, b"sent");
+        let proof = proof(&client_state, scheme, &trie, &commitment_path(&sent));
+
+        client_state
+            .verify_packet_commitment(&ctx, &client_id(), &height(), &prefix(), &proof, &sent)
+            .unwrap();
+        let tampered = packet(1, b"tampered");
+        assert!(client_state
+            .verify_packet_commitment(&ctx, &client_id(), &height(), &prefix(), &proof, &tampered)
+            .is_err());
+    }
+}
+
+#[test]
+fn packet_acknowledgement_is_verified() {
+    for scheme in SCHEMES {
+        let (client_state, trie, ctx) = setup(scheme);
+        let received = packet(2, b"received");
+        let proof = proof(&client_state, scheme, &trie, &ack_path(&received));
+
+        client_state
+            .verify_packet_acknowledgement(
+                &ctx,
+                &client_id(),
+                &height(),
+                &prefix(),
+                &proof,
+                &received,
+                &ack(),
+            )
+            .unwrap();
+        let other_ack = Acknowledgement::try_from(b"{\"error\":\"failed\"}".to_vec()).unwrap();
+        assert!(client_state
+            .verify_packet_acknowledgement(
+                &ctx,
+                &client_id(),
+                &height(),
+                &prefix(),
+                &proof,
+                &received,
+                &other_ack,
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn packet_receipt_absence_is_verified() {
+    for scheme in SCHEMES {
+        let (client_state, trie, ctx) = setup(scheme);
+        let unreceived = packet(3, b"unreceived");
+        let proof_of_absence = proof(&client_state, scheme, &trie, &receipt_path(&unreceived));
+        client_state
+            .verify_packet_receipt_absence(
+                &ctx,
+                &client_id(),
+                &height(),
+                &prefix(),
+                &proof_of_absence,
+                &unreceived,
+            )
+            .unwrap();
+
+        let received = packet(2, b"received");
+        let proof_of_receipt = proof(&client_state, scheme, &trie, &receipt_path(&received));
+        assert!(client_state
+            .verify_packet_receipt_absence(
+                &ctx,
+                &client_id(),
+                &height(),
+                &prefix(),
+                &proof_of_receipt,
+                &received,
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn next_sequence_recv_is_verified_as_stored_by_the_scheme() {
+    for scheme in SCHEMES {
+        let (client_state, trie, ctx) = setup(scheme);
+        let proof = proof(&client_state, scheme, &trie, &seq_recv_path());
+        let verify = |next_seq_recv: u64| {
+            client_state.verify_next_sequence_recv(
+                &ctx,
+                &client_id(),
+                &height(),
+                &prefix(),
+                &proof,
+                &PortId::transfer(),
+                &ChannelId::new(1),
+                Sequence::from(next_seq_recv),
+            )
+        };
+
+        verify(3).unwrap();
+        assert!(verify(2).is_err());
+        // The bytes of the sequence are not reversed by the scheme.
+        assert!(verify(3 << 56).is_err());
+    }
+}
+
+#[test]
+fn packet_is_not_verified_without_consensus_state() {
+    let scheme = StorageKeyScheme::PlainString;
+    let (client_state, trie, ctx) = setup(scheme);
+    let sent = packet(1, b"sent");
+    let proof = proof(&client_state, scheme, &trie, &commitment_path(&sent));
+
+    let other_height = Height::new(0, HEIGHT + 1).unwrap();
+    assert!(client_state
+        .verify_packet_commitment(&ctx, &client_id(), &other_height, &prefix(), &proof, &sent)
+        .is_err());
+}
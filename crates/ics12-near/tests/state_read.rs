@@ -0,0 +1,317 @@
+//! Verified reads of the storage of any contract on NEAR, against the trusted
+//! consensus states of the client.
+
+mod common;
+
+use common::{client_id, MockContext};
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::CommitmentProofBytes;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::near_types::get_raw_prefix_for_contract_data;
+use ics12_near_types::v1::near_types::trie::{builder::InMemoryTrie, ProofLimits, ValueRef};
+use ics12_near_types::v1::near_types::AccountId;
+use ics12_near_types::v1::state_proof::NearStateProof;
+
+const HEIGHT: u64 = 100;
+const TRUSTING_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+fn client_state() -> ClientState {
+    ClientStateType::new_without_validation(TRUSTING_PERIOD, height(), 0).into()
+}
+
+fn height() -> Height {
+    Height::new(0, HEIGHT).unwrap()
+}
+
+fn token() -> AccountId {
+    "token.near".to_string()
+}
+
+fn other_token() -> AccountId {
+    "other-token.near".to_string()
+}
+
+/// Returns the state of two token contracts, with the balance of `alice.near`
+/// in each of them.
+fn trie() -> InMemoryTrie {
+    [
+        (
+            get_raw_prefix_for_contract_data(&token(), b"balance:alice.near"),
+            100_u128.to_le_bytes().to_vec(),
+        ),
+        (
+            get_raw_prefix_for_contract_data(&token(), b"balance:bob.near"),
+            5_u128.to_le_bytes().to_vec(),
+        ),
+        (
+            get_raw_prefix_for_contract_data(&other_token(), b"balance:alice.near"),
+            7_u128.to_le_bytes().to_vec(),
+        ),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn proof(trie: &InMemoryTrie, account_id: &AccountId, key: &[u8]) -> (usize, CommitmentProofBytes) {
+    let nodes: Vec<Vec<u8>> = trie
+        .prove(&get_raw_prefix_for_contract_data(account_id, key))
+        .iter()
+        .map(|node| node.encode())
+        .collect();
+    (nodes.len(), NearStateProof::new(0, nodes).into())
+}
+
+fn ctx(host_height: u64, trie: &InMemoryTrie) -> MockContext {
+    MockContext::new(host_height).with_consensus_state(HEIGHT, trie.root())
+}
+
+#[test]
+fn contract_storage_value_is_verified() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:alice.near");
+
+    client_state()
+        .verify_contract_storage(
+            &ctx(HEIGHT, &trie),
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:alice.near",
+            Some(&100_u128.to_le_bytes()),
+            &proof,
+            &ProofLimits::default(),
+        )
+        .unwrap();
+}
+
+#[test]
+fn wrong_contract_storage_value_is_rejected() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:alice.near");
+    let ctx = ctx(HEIGHT, &trie);
+
+    for value in [Some(7_u128.to_le_bytes()), None] {
+        assert!(client_state()
+            .verify_contract_storage(
+                &ctx,
+                &client_id(),
+                &height(),
+                &token(),
+                b"balance:alice.near",
+                value.as_ref().map(|value| value.as_slice()),
+                &proof,
+                &ProofLimits::default(),
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn value_of_other_contract_is_rejected() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &other_token(), b"balance:alice.near");
+
+    assert!(client_state()
+        .verify_contract_storage(
+            &ctx(HEIGHT, &trie),
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:alice.near",
+            Some(&7_u128.to_le_bytes()),
+            &proof,
+            &ProofLimits::default(),
+        )
+        .is_err());
+}
+
+#[test]
+fn absent_contract_storage_value_is_verified() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:carol.near");
+    let ctx = ctx(HEIGHT, &trie);
+
+    client_state()
+        .verify_contract_storage(
+            &ctx,
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:carol.near",
+            None,
+            &proof,
+            &ProofLimits::default(),
+        )
+        .unwrap();
+    assert!(client_state()
+        .verify_contract_storage(
+            &ctx,
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:carol.near",
+            Some(&0_u128.to_le_bytes()),
+            &proof,
+            &ProofLimits::default(),
+        )
+        .is_err());
+}
+
+#[test]
+fn contract_storage_proof_over_limits_is_rejected() {
+    let trie = trie();
+    let (node_count, proof) = proof(&trie, &token(), b"balance:alice.near");
+
+    assert!(client_state()
+        .verify_contract_storage(
+            &ctx(HEIGHT, &trie),
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:alice.near",
+            Some(&100_u128.to_le_bytes()),
+            &proof,
+            &ProofLimits {
+                max_nodes: node_count - 1,
+                ..ProofLimits::default()
+            },
+        )
+        .is_err());
+}
+
+#[test]
+fn contract_storage_is_rejected_after_the_trusting_period() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:alice.near");
+
+    assert!(client_state()
+        .verify_contract_storage(
+            &ctx(HEIGHT + TRUSTING_PERIOD.as_secs() + 1, &trie),
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:alice.near",
+            Some(&100_u128.to_le_bytes()),
+            &proof,
+            &ProofLimits::default(),
+        )
+        .is_err());
+}
+
+#[test]
+fn contract_storage_value_ref_is_verified() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:alice.near");
+    let ctx = ctx(HEIGHT, &trie);
+    let value_ref = ValueRef::of(&100_u128.to_le_bytes());
+
+    client_state()
+        .verify_contract_storage_value_ref(
+            &ctx,
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:alice.near",
+            &value_ref,
+            &proof,
+            &ProofLimits::default(),
+        )
+        .unwrap();
+    assert_eq!(
+        client_state()
+            .contract_storage_value_ref(
+                &ctx,
+                &client_id(),
+                &height(),
+                &token(),
+                b"balance:alice.near",
+                &proof,
+                &ProofLimits::default(),
+            )
+            .unwrap(),
+        Some(value_ref)
+    );
+}
+
+#[test]
+fn wrong_contract_storage_value_ref_is_rejected() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:alice.near");
+    let ctx = ctx(HEIGHT, &trie);
+    let value_ref = ValueRef::of(&100_u128.to_le_bytes());
+
+    for wrong_value_ref in [
+        ValueRef::of(&7_u128.to_le_bytes()),
+        ValueRef {
+            length: value_ref.length - 1,
+            ..value_ref
+        },
+    ] {
+        assert!(client_state()
+            .verify_contract_storage_value_ref(
+                &ctx,
+                &client_id(),
+                &height(),
+                &token(),
+                b"balance:alice.near",
+                &wrong_value_ref,
+                &proof,
+                &ProofLimits::default(),
+            )
+            .is_err());
+    }
+}
+
+#[test]
+fn value_ref_of_absent_contract_storage_is_rejected() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:carol.near");
+    let ctx = ctx(HEIGHT, &trie);
+
+    assert!(client_state()
+        .verify_contract_storage_value_ref(
+            &ctx,
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:carol.near",
+            &ValueRef::of(&0_u128.to_le_bytes()),
+            &proof,
+            &ProofLimits::default(),
+        )
+        .is_err());
+    assert_eq!(
+        client_state()
+            .contract_storage_value_ref(
+                &ctx,
+                &client_id(),
+                &height(),
+                &token(),
+                b"balance:carol.near",
+                &proof,
+                &ProofLimits::default(),
+            )
+            .unwrap(),
+        None
+    );
+}
+
+#[test]
+fn contract_storage_value_ref_is_rejected_after_the_trusting_period() {
+    let trie = trie();
+    let (_, proof) = proof(&trie, &token(), b"balance:alice.near");
+
+    assert!(client_state()
+        .contract_storage_value_ref(
+            &ctx(HEIGHT + TRUSTING_PERIOD.as_secs() + 1, &trie),
+            &client_id(),
+            &height(),
+            &token(),
+            b"balance:alice.near",
+            &proof,
+            &ProofLimits::default(),
+        )
+        .is_err());
+}
@@ -0,0 +1,62 @@
+//! Detection of misbehaviour in the headers of client updates, against the
+//! consensus states stored around their height.
+
+mod common;
+
+use common::{client_id, header, MockContext};
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::header::Header;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+
+fn client_state(latest_height: u64) -> ClientState {
+    ClientStateType::new_without_validation(
+        Duration::from_secs(60 * 60),
+        Height::new(0, latest_height).unwrap(),
+        0,
+    )
+    .into()
+}
+
+/// Returns the header at `height` committing to the block producers of the
+/// next epoch with `next_bp_hash`.
+fn header_with_next_bp_hash(height: u64, next_bp_hash: u8) -> Header {
+    let mut header = header(height, CryptoHash([0; 32]));
+    header.light_client_block.inner_lite.next_bp_hash = CryptoHash([next_bp_hash; 32]);
+    header
+}
+
+#[test]
+fn header_conflicting_with_previous_next_block_producers_is_misbehaviour() {
+    let ctx = MockContext::new(30).with_trusted_header(None, header_with_next_bp_hash(10, 3));
+    let client_state = client_state(10);
+
+    assert!(!client_state
+        .check_for_misbehaviour_update_client(&ctx, &client_id(), header_with_next_bp_hash(20, 3))
+        .unwrap());
+    assert!(client_state
+        .check_for_misbehaviour_update_client(&ctx, &client_id(), header_with_next_bp_hash(20, 4))
+        .unwrap());
+}
+
+#[test]
+fn header_conflicting_with_next_next_block_producers_is_misbehaviour() {
+    let ctx = MockContext::new(40)
+        .with_trusted_header(None, header_with_next_bp_hash(10, 3))
+        .with_trusted_header(None, header_with_next_bp_hash(30, 4));
+    let client_state = client_state(30);
+
+    // The header at 20 can't agree with both consensus states around it.
+    assert!(client_state
+        .check_for_misbehaviour_update_client(&ctx, &client_id(), header_with_next_bp_hash(20, 3))
+        .unwrap());
+
+    let ctx = MockContext::new(40)
+        .with_trusted_header(None, header_with_next_bp_hash(10, 3))
+        .with_trusted_header(None, header_with_next_bp_hash(30, 3));
+    assert!(!client_state
+        .check_for_misbehaviour_update_client(&ctx, &client_id(), header_with_next_bp_hash(20, 3))
+        .unwrap());
+}
@@ -0,0 +1,184 @@
+//! Verification of the proofs of `view_state` query responses of the IBC
+//! contract `ibc.testnet`, with the commitment prefix `ibc`, in shard 0.
+//!
+//! The two fixtures hold the same entries: `view_state_proof.json` has the
+//! trie nodes of all of them in its top-level `proof`, as returned by current
+//! NEAR nodes, and `view_state_item_proof.json` has the nodes of each entry
+//! in its own `proof`, as returned by older ones.
+
+use core::time::Duration;
+use hex_literal::hex;
+use ibc_core::client::context::client_state::ClientStateCommon;
+use ibc_core::client::types::Height;
+use ibc_core::commitment_types::commitment::{CommitmentPrefix, CommitmentRoot};
+use ibc_core::host::types::identifiers::{ChannelId, PortId, Sequence};
+use ibc_core::host::types::path::{AckPath, CommitmentPath, Path, ReceiptPath, SeqRecvPath};
+use ics12_near::v1::client_state::ClientState;
+use ics12_near_types::v1::client_state::ClientState as ClientStateType;
+use ics12_near_types::v1::near_types::get_raw_prefix_for_contract_data;
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::state_proof::view_state::ViewStateResult;
+
+const VIEW_STATE_PROOF: &str = include_str!("fixtures/view_state_proof.json");
+const VIEW_STATE_ITEM_PROOF: &str = include_str!("fixtures/view_state_item_proof.json");
+
+const IBC_CONTRACT: &str = "ibc.testnet";
+const SHARD_ID: u64 = 0;
+const STATE_ROOT: CryptoHash = CryptoHash(hex!(
+    "1cbf28ce3c3d0e1a1ac7798dc1799fb8ef159b99d4c0e61443b8d2cdbfdea511"
+));
+
+fn client_state() -> ClientState {
+    ClientStateType::new_without_validation(
+        Duration::from_secs(14 * 24 * 60 * 60),
+        Height::new(0, 152336148).unwrap(),
+        0,
+    )
+    .with_ibc_contract_account_id(IBC_CONTRACT.to_string())
+    .into()
+}
+
+fn prefix() -> CommitmentPrefix {
+    CommitmentPrefix::try_from(b"ibc".to_vec()).unwrap()
+}
+
+fn root() -> CommitmentRoot {
+    CommitmentRoot::from_bytes(&borsh::to_vec(&vec![STATE_ROOT]).unwrap())
+}
+
+fn commitment_path(sequence: u64) -> Path {
+    Path::Commitment(CommitmentPath {
+        port_id: PortId::transfer(),
+        channel_id: ChannelId::new(0),
+        sequence: Sequence::from(sequence),
+    })
+}
+
+fn receipt_path(sequence: u64) -> Path {
+    Path::Receipt(ReceiptPath {
+        port_id: PortId::transfer(),
+        channel_id: ChannelId::new(0),
+        sequence: Sequence::from(sequence),
+    })
+}
+
+fn ack_path(sequence: u64) -> Path {
+    Path::Ack(AckPath {
+        port_id: PortId::transfer(),
+        channel_id: ChannelId::new(0),
+        sequence: Sequence::from(sequence),
+    })
+}
+
+/// Returns the key of the contract storage of `path`.
+fn storage_key(path: &Path) -> Vec<u8> {
+    let mut key = b"ibc".to_vec();
+    key.extend(path.to_string().into_bytes());
+    key
+}
+
+fn verify_membership(result: &ViewStateResult, path: Path) {
+    let key = storage_key(&path);
+    let value = result.value(&key).unwrap().to_vec();
+    let proof = result
+        .commitment_proof(&IBC_CONTRACT.to_string(), &key, SHARD_ID, &STATE_ROOT)
+        .unwrap();
+    client_state()
+        .verify_membership(&prefix(), &proof, &root(), path, value)
+        .unwrap();
+}
+
+fn verify_non_membership(result: &ViewStateResult, path: Path) {
+    let key = storage_key(&path);
+    assert_eq!(result.value(&key), None);
+    let proof = result
+        .commitment_proof(&IBC_CONTRACT.to_string(), &key, SHARD_ID, &STATE_ROOT)
+        .unwrap();
+    client_state()
+        .verify_non_membership(&prefix(), &proof, &root(), path)
+        .unwrap();
+}
+
+#[test]
+fn top_level_proof_verifies() {
+    let result = ViewStateResult::from_json(VIEW_STATE_PROOF).unwrap();
+    assert_eq!(result.values.len(), 6);
+
+    verify_membership(&result, commitment_path(1));
+    verify_membership(&result, commitment_path(2));
+    verify_membership(&result, ack_path(1));
+    verify_membership(&result, receipt_path(1));
+    verify_membership(
+        &result,
+        Path::SeqRecv(SeqRecvPath(PortId::transfer(), ChannelId::new(0))),
+    );
+    verify_non_membership(&result, commitment_path(3));
+    verify_non_membership(&result, receipt_path(2));
+}
+
+#[test]
+fn item_proofs_verify() {
+    let result = ViewStateResult::from_json(VIEW_STATE_ITEM_PROOF).unwrap();
+    let top_level = ViewStateResult::from_json(VIEW_STATE_PROOF).unwrap();
+    assert_eq!(result.values, top_level.values);
+    // The nodes shared by several entries are only kept once.
+    assert_eq!(result.proof.len(), top_level.proof.len());
+    assert!(result
+        .proof
+        .iter()
+        .all(|node| top_level.proof.contains(node)));
+
+    verify_membership(&result, commitment_path(1));
+    verify_membership(&result, ack_path(1));
+    verify_non_membership(&result, commitment_path(3));
+}
+
+#[test]
+fn proof_of_other_value_fails() {
+    let result = ViewStateResult::from_json(VIEW_STATE_PROOF).unwrap();
+    let path = commitment_path(1);
+    let key = storage_key(&path);
+    let proof = result
+        .commitment_proof(&IBC_CONTRACT.to_string(), &key, SHARD_ID, &STATE_ROOT)
+        .unwrap();
+    let other_value = result.value(&storage_key(&commitment_path(2))).unwrap();
+
+    assert!(client_state()
+        .verify_membership(
+            &prefix(),
+            &proof,
+            &root(),
+            path.clone(),
+            other_value.to_vec()
+        )
+        .is_err());
+    assert!(client_state()
+        .verify_non_membership(&prefix(), &proof, &root(), path)
+        .is_err());
+}
+
+#[test]
+fn off_path_nodes_are_dropped() {
+    let result = ViewStateResult::from_json(VIEW_STATE_PROOF).unwrap();
+    let trie_key = |path: &Path| {
+        get_raw_prefix_for_contract_data(&IBC_CONTRACT.to_string(), &storage_key(path))
+    };
+    let nodes = result
+        .state_proof(&trie_key(&commitment_path(1)), SHARD_ID, &STATE_ROOT)
+        .unwrap()
+        .validate()
+        .unwrap();
+    let ack_nodes = result
+        .state_proof(&trie_key(&ack_path(1)), SHARD_ID, &STATE_ROOT)
+        .unwrap()
+        .validate()
+        .unwrap();
+
+    assert!(nodes.len() < result.proof.len());
+    assert!(nodes.iter().all(|node| result.proof.contains(node)));
+    // The leaf of the acknowledgement is in the response but not on the path
+    // of the packet commitment.
+    let ack_leaf = ack_nodes.last().unwrap();
+    assert!(result.proof.contains(ack_leaf));
+    assert!(!nodes.contains(ack_leaf));
+}
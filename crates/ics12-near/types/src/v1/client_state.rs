@@ -1,4 +1,8 @@
+use super::near_types::{
+    get_raw_prefix_for_contract_data, hash::CryptoHash, trie::ProofLimits, AccountId,
+};
 use super::{error::Error as Ics12Error, header::Header as NearHeader};
+use alloc::format;
 use alloc::string::ToString;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -7,12 +11,28 @@ use ibc_core::client::types::error::ClientError;
 use ibc_core::client::types::Height;
 use ibc_core::primitives::ZERO_DURATION;
 use ibc_proto::{google::protobuf::Any, Protobuf};
-use ics12_proto::v1::ClientState as RawClientState;
-use prost::Message;
+use ics12_near_proto::v1::{ClientState as RawClientState, ProofLimits as RawProofLimits};
+use ics12_near_proto::Message;
 use serde::{Deserialize, Serialize};
 
 pub const NEAR_CLIENT_STATE_TYPE_URL: &str = "/ibc.lightclients.near.v1.ClientState";
 
+/// Number of blocks of an epoch on NEAR mainnet and testnet.
+pub const EPOCH_LENGTH: u64 = 43200;
+
+/// Upper bound of the time between two NEAR blocks, used to derive the
+/// default epoch duration. Blocks of NEAR mainnet are produced every 1.2 to
+/// 1.3 seconds, so this leaves a margin for slower periods.
+pub const MAX_BLOCK_TIME: Duration = Duration::from_secs(2);
+
+/// Default duration of a NEAR epoch, [`EPOCH_LENGTH`] blocks of
+/// [`MAX_BLOCK_TIME`], i.e. 24 hours.
+///
+/// Overestimating the epoch duration only delays the detection of a stale
+/// epoch, while underestimating it would report a live client as stale.
+pub const DEFAULT_EPOCH_DURATION: Duration =
+    Duration::from_secs(EPOCH_LENGTH * MAX_BLOCK_TIME.as_secs());
+
 #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub struct ClientState {
     pub trusting_period: Duration,
@@ -26,6 +46,55 @@ pub struct ClientState {
     pub upgrade_commitment_prefix: Vec<u8>,
     ///
     pub upgrade_key: Vec<u8>,
+    /// Expected duration of a NEAR epoch, used to detect a client that has
+    /// fallen too far behind the chain to be updated again
+    pub epoch_duration: Duration,
+    /// Account id of the IBC contract on NEAR, whose contract data holds the
+    /// IBC commitments. `None` if commitment prefixes hold the whole raw
+    /// prefix of the contract data instead.
+    pub ibc_contract_account_id: Option<AccountId>,
+    /// How the IBC contract stores commitments under their paths
+    pub storage_key_scheme: StorageKeyScheme,
+    /// Expected code hash of the IBC contract. If set, commitment proofs must
+    /// also prove that the contract still has this code.
+    pub ibc_contract_code_hash: Option<CryptoHash>,
+    /// Limits of the state proofs verified by the client
+    pub proof_limits: ProofLimits,
+}
+
+/// How the IBC contract on NEAR derives the keys of its storage from the
+/// commitment prefix and the ICS-24 path of a commitment.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum StorageKeyScheme {
+    /// The commitment prefix followed by the path as a string
+    #[default]
+    PlainString,
+    /// Entries of a near-sdk `LookupMap` keyed by the path string, with the
+    /// commitment prefix as prefix of the map
+    LookupMapBorsh,
+}
+
+impl From<StorageKeyScheme> for i32 {
+    fn from(value: StorageKeyScheme) -> Self {
+        match value {
+            StorageKeyScheme::PlainString => 0,
+            StorageKeyScheme::LookupMapBorsh => 1,
+        }
+    }
+}
+
+impl TryFrom<i32> for StorageKeyScheme {
+    type Error = Ics12Error;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::PlainString),
+            1 => Ok(Self::LookupMapBorsh),
+            _ => Err(Ics12Error::InvalidRawClientState {
+                reason: format!("unknown storage key scheme {}", value),
+            }),
+        }
+    }
 }
 
 impl ClientState {
@@ -41,6 +110,11 @@ impl ClientState {
             latest_timestamp,
             upgrade_commitment_prefix: vec![],
             upgrade_key: vec![],
+            epoch_duration: DEFAULT_EPOCH_DURATION,
+            ibc_contract_account_id: None,
+            storage_key_scheme: StorageKeyScheme::default(),
+            ibc_contract_code_hash: None,
+            proof_limits: ProofLimits::default(),
         }
     }
     ///
@@ -58,6 +132,41 @@ impl ClientState {
         }
     }
     ///
+    pub fn with_epoch_duration(self, epoch_duration: Duration) -> Self {
+        Self {
+            epoch_duration,
+            ..self
+        }
+    }
+    ///
+    pub fn with_ibc_contract_account_id(self, ibc_contract_account_id: AccountId) -> Self {
+        Self {
+            ibc_contract_account_id: Some(ibc_contract_account_id),
+            ..self
+        }
+    }
+    ///
+    pub fn with_storage_key_scheme(self, storage_key_scheme: StorageKeyScheme) -> Self {
+        Self {
+            storage_key_scheme,
+            ..self
+        }
+    }
+    ///
+    pub fn with_ibc_contract_code_hash(self, ibc_contract_code_hash: CryptoHash) -> Self {
+        Self {
+            ibc_contract_code_hash: Some(ibc_contract_code_hash),
+            ..self
+        }
+    }
+    ///
+    pub fn with_proof_limits(self, proof_limits: ProofLimits) -> Self {
+        Self {
+            proof_limits,
+            ..self
+        }
+    }
+    ///
     pub fn with_frozen_height(self, h: Height) -> Self {
         Self {
             frozen_height: Some(h),
@@ -73,6 +182,32 @@ impl ClientState {
     pub fn is_frozen(&self) -> bool {
         self.frozen_height.is_some()
     }
+
+    /// Returns `true` if `elapsed`, the host time passed since the latest
+    /// consensus state, means the chain has left the epoch following the one
+    /// of that consensus state.
+    ///
+    /// Headers are only accepted for the epoch of the latest consensus state
+    /// or the next one. As the epoch of the latest consensus state may have
+    /// just started, the chain is only known to be past the next epoch once
+    /// two epoch durations have elapsed.
+    pub fn is_epoch_stale(&self, elapsed: Duration) -> bool {
+        self.epoch_duration
+            .checked_mul(2)
+            .is_some_and(|max_elapsed| elapsed > max_elapsed)
+    }
+
+    /// Returns the raw trie key of `key`, a key of the storage of the IBC
+    /// contract.
+    ///
+    /// Without an IBC contract account id, `key` is expected to already be a
+    /// raw trie key, and is returned as is.
+    pub fn contract_storage_key(&self, key: &[u8]) -> Vec<u8> {
+        match &self.ibc_contract_account_id {
+            Some(account_id) => get_raw_prefix_for_contract_data(account_id, key),
+            None => key.to_vec(),
+        }
+    }
 }
 
 impl Protobuf<RawClientState> for ClientState {}
@@ -104,16 +239,89 @@ impl TryFrom<RawClientState> for ClientState {
             return Err(Ics12Error::FrozenHeightNotAllowed);
         }
 
+        let epoch_duration = match value.epoch_duration {
+            Some(epoch_duration) => {
+                epoch_duration
+                    .try_into()
+                    .map_err(|_| Ics12Error::InvalidRawClientState {
+                        reason: "invalid epoch duration".to_string(),
+                    })?
+            }
+            None => DEFAULT_EPOCH_DURATION,
+        };
+
         let client_state = ClientState::new_without_validation(
             trusting_period,
             latest_height,
             value.latest_timestamp,
-        );
+        )
+        .with_epoch_duration(epoch_duration)
+        .with_storage_key_scheme(value.storage_key_scheme.try_into()?);
+        let client_state = match value.ibc_contract_account_id.is_empty() {
+            true => client_state,
+            false => client_state.with_ibc_contract_account_id(value.ibc_contract_account_id),
+        };
+        // The code hash is checked against the account of the IBC contract,
+        // so a pin without it could never be enforced.
+        let client_state = match value.ibc_contract_code_hash.is_empty() {
+            true => client_state,
+            false if client_state.ibc_contract_account_id.is_none() => {
+                return Err(Ics12Error::InvalidRawClientState {
+                    reason: "IBC contract code hash is pinned without IBC contract account id"
+                        .to_string(),
+                })
+            }
+            false => client_state.with_ibc_contract_code_hash(
+                CryptoHash::try_from(value.ibc_contract_code_hash.as_slice()).map_err(
+                    |reason| Ics12Error::InvalidRawClientState {
+                        reason: format!("invalid IBC contract code hash: {}", reason),
+                    },
+                )?,
+            ),
+        };
+
+        let client_state = match value.proof_limits {
+            Some(proof_limits) => {
+                let proof_limits = ProofLimits::try_from(proof_limits)?;
+                proof_limits.validate()?;
+                client_state.with_proof_limits(proof_limits)
+            }
+            None => client_state,
+        };
 
         Ok(client_state)
     }
 }
 
+impl TryFrom<RawProofLimits> for ProofLimits {
+    type Error = Ics12Error;
+
+    fn try_from(value: RawProofLimits) -> Result<Self, Self::Error> {
+        let limit = |name: &str, value: u64| {
+            usize::try_from(value).map_err(|_| Ics12Error::InvalidProofLimits {
+                reason: format!("{} {} does not fit in usize", name, value),
+            })
+        };
+        Ok(Self {
+            max_nodes: limit("maximum number of nodes", value.max_nodes)?,
+            max_total_bytes: limit("maximum proof size", value.max_total_bytes)?,
+            max_key_length: limit("maximum key length", value.max_key_length)?,
+            max_nibble_depth: limit("maximum nibble depth", value.max_nibble_depth)?,
+        })
+    }
+}
+
+impl From<ProofLimits> for RawProofLimits {
+    fn from(value: ProofLimits) -> Self {
+        Self {
+            max_nodes: value.max_nodes as u64,
+            max_total_bytes: value.max_total_bytes as u64,
+            max_key_length: value.max_key_length as u64,
+            max_nibble_depth: value.max_nibble_depth as u64,
+        }
+    }
+}
+
 impl From<ClientState> for RawClientState {
     fn from(value: ClientState) -> Self {
         Self {
@@ -123,6 +331,14 @@ impl From<ClientState> for RawClientState {
             latest_timestamp: value.latest_timestamp,
             upgrade_commitment_prefix: value.upgrade_commitment_prefix,
             upgrade_key: value.upgrade_key,
+            epoch_duration: Some(value.epoch_duration.into()),
+            ibc_contract_account_id: value.ibc_contract_account_id.unwrap_or_default(),
+            storage_key_scheme: value.storage_key_scheme.into(),
+            ibc_contract_code_hash: value
+                .ibc_contract_code_hash
+                .map(|code_hash| code_hash.0.to_vec())
+                .unwrap_or_default(),
+            proof_limits: Some(value.proof_limits.into()),
         }
     }
 }
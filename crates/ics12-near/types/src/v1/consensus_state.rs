@@ -10,10 +10,10 @@ use borsh::BorshDeserialize;
 use ibc_core::client::types::error::ClientError;
 use ibc_core::commitment_types::commitment::CommitmentRoot;
 use ibc_proto::{google::protobuf::Any, Protobuf};
-use ics12_proto::v1::{
+use ics12_near_proto::v1::{
     ConsensusState as RawConsensusState, ValidatorStakeView as RawValidatorStakeView,
 };
-use prost::Message;
+use ics12_near_proto::Message;
 use serde::{Deserialize, Serialize};
 
 pub const NEAR_CONSENSUS_STATE_TYPE_URL: &str = "/ibc.lightclients.near.v1.ConsensusState";
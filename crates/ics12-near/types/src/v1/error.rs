@@ -1,5 +1,6 @@
 //! Defines the near light client's error type
 
+use super::near_types::hash::CryptoHash;
 use alloc::string::String;
 use alloc::string::ToString;
 use core::time::Duration;
@@ -48,7 +49,7 @@ pub enum Error {
     /// invalid raw misbehaviour: `{reason}`
     InvalidRawMisbehaviour { reason: String },
     /// decode error: `{0}`
-    Decode(prost::DecodeError),
+    Decode(ics12_near_proto::DecodeError),
     /// given other previous updates, header timestamp should be at most `{max}`, but was `{actual}`
     HeaderTimestampTooHigh { actual: String, max: String },
     /// given other previous updates, header timestamp should be at least `{min}`, but was `{actual}`
@@ -77,18 +78,73 @@ pub enum Error {
     MisbehaviourHeadersBlockHashesEqual,
     /// headers are not at same height and are monotonically increasing
     MisbehaviourHeadersNotAtSameHeight,
+    /// ancestry proof does not show that the headers of the misbehaviour are on different forks
+    InvalidAncestryProof,
     /// invalid raw client id: `{client_id}`
     InvalidRawClientId { client_id: String },
     /// missing proof data
     MissingProofData,
     /// invalid root hash of proof data
     InvalidRootHashOfProofData,
+    /// invalid shard id `{shard_id}` of proof data
+    InvalidShardIdOfProofData { shard_id: u64 },
     /// invalid proof data
     InvalidProofData { proof_index: u16 },
     /// invalid proof data length
     InvalidProofDataLength,
+    /// unused proof data at index `{proof_index}`
+    UnusedProofData { proof_index: u16 },
+    /// duplicate proof data at index `{proof_index}`
+    DuplicateProofData { proof_index: u16 },
+    /// proof of `{size}` bytes exceeds the maximum of `{max}` bytes
+    ProofTooLarge { size: usize, max: usize },
+    /// proof with `{count}` nodes exceeds the maximum of `{max}` nodes
+    TooManyProofNodes { count: usize, max: usize },
+    /// key of `{length}` bytes of proof data at index `{proof_index}` exceeds the maximum of `{max}` bytes
+    ProofNodeKeyTooLong {
+        proof_index: u16,
+        length: usize,
+        max: usize,
+    },
+    /// invalid proof limits: `{reason}`
+    InvalidProofLimits { reason: String },
+    /// key of `{depth}` nibbles exceeds the maximum depth of `{max}` nibbles
+    ProofKeyTooDeep { depth: usize, max: usize },
     /// specified key has value in state
     SpecifiedKeyHasValueInState,
+    /// a key with the specified prefix has value in state
+    KeyWithSpecifiedPrefixInState,
+    /// a key in the specified range has value in state
+    KeyInSpecifiedRangeInState,
+    /// invalid key range, the start key must be lower than the end key
+    InvalidKeyRange,
+    /// execution outcome is not included in the outcome root of its block
+    InvalidOutcomeProof,
+    /// block of the execution outcome does not match the one of its proof
+    InvalidOutcomeBlockHash,
+    /// block of the execution outcome is not included in the trusted block merkle root
+    InvalidBlockProof,
+    /// execution outcome was executed by `{actual}` instead of `{expected}`
+    MismatchOutcomeExecutorId { expected: String, actual: String },
+    /// missing log `{index}` in execution outcome
+    MissingOutcomeLog { index: usize },
+    /// invalid NEP-297 event log: `{reason}`
+    InvalidEventLog { reason: String },
+    /// invalid transaction proof: `{reason}`
+    InvalidTransactionProof { reason: String },
+    /// invalid receipt proof: `{reason}`
+    InvalidReceiptProof { reason: String },
+    /// missing proof of the account of the IBC contract
+    MissingAccountProof,
+    /// code hash `{actual}` of the IBC contract does not match the expected `{expected}`
+    MismatchContractCodeHash {
+        expected: CryptoHash,
+        actual: CryptoHash,
+    },
+    /// invalid `view_state` RPC response: `{reason}`
+    InvalidViewStateResponse { reason: String },
+    /// invalid ICS-23 proof: `{reason}`
+    InvalidIcs23Proof { reason: String },
     /// failed to deserialize with borsh
     BorshDeserializeError,
     /// failed to serialize with borsh
@@ -12,8 +12,8 @@ use ibc_core::client::types::error::ClientError;
 use ibc_core::client::types::Height;
 use ibc_core::primitives::Timestamp;
 use ibc_proto::{google::protobuf::Any, Protobuf};
-use ics12_proto::v1::{CryptoHash as RawCryptoHash, Header as RawHeader};
-use prost::Message;
+use ics12_near_proto::v1::{CryptoHash as RawCryptoHash, Header as RawHeader};
+use ics12_near_proto::Message;
 use serde::{Deserialize, Serialize};
 
 pub const NEAR_HEADER_TYPE_URL: &str = "/ibc.lightclients.near.v1.Header";
@@ -38,6 +38,16 @@ impl Header {
     pub fn raw_timestamp(&self) -> u64 {
         self.light_client_block.inner_lite.timestamp
     }
+    /// Returns `true` if `other` is for the same next epoch as this header, but
+    /// commits to different block producers for it.
+    ///
+    /// All blocks sharing a `next_epoch_id` carry the same `next_bp_hash`, so
+    /// this is evidence of a fork of the validator set.
+    pub fn has_conflicting_next_bps(&self, other: &Header) -> bool {
+        self.next_epoch_id() == other.next_epoch_id()
+            && self.light_client_block.inner_lite.next_bp_hash
+                != other.light_client_block.inner_lite.next_bp_hash
+    }
 }
 
 impl Header {
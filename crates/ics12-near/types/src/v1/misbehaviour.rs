@@ -1,11 +1,21 @@
-use super::{error::Error, header::Header as NearHeader};
+use super::{
+    error::Error,
+    header::Header as NearHeader,
+    near_types::{
+        merkle::{verify_hash, MerklePath},
+        Balance, LightClientBlockLite, ValidatorStakeView, ValidatorStakeViewV1,
+    },
+};
+use alloc::format;
 use alloc::string::ToString;
+use alloc::vec::Vec;
+use borsh::{to_vec, BorshDeserialize, BorshSerialize};
 use bytes::Buf;
 use ibc_core::client::types::error::ClientError;
 use ibc_core::host::types::identifiers::ClientId;
 use ibc_proto::{google::protobuf::Any, Protobuf};
-use ics12_proto::v1::Misbehaviour as RawMisbehaviour;
-use prost::Message;
+use ics12_near_proto::v1::Misbehaviour as RawMisbehaviour;
+use ics12_near_proto::Message;
 use serde::{Deserialize, Serialize};
 
 const NEAR_MISBEHAVIOUR_TYPE_URL: &str = "/ibc.lightclients.near.v1.Misbehaviour";
@@ -15,14 +25,97 @@ pub struct Misbehaviour {
     client_id: ClientId,
     header1: NearHeader,
     header2: NearHeader,
+    /// Proof that `header2` is not an ancestor of `header1`
+    ancestry_proof: Option<AncestryProof>,
+}
+
+/// What the headers of a misbehaviour disagree on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MisbehaviourKind {
+    /// Different blocks at the same height
+    ConflictingBlocks,
+    /// Different block producers for the same next epoch
+    ConflictingEpochs,
+    /// Blocks at different heights on different forks, as shown by the
+    /// ancestry proof of the misbehaviour
+    Fork,
+    /// Timestamps which do not increase with the heights
+    ConflictingTimestamps,
+}
+
+/// Proof that the lower header of a misbehaviour is not an ancestor of the
+/// higher one.
+///
+/// It consists of a block of the chain of the higher header, at the height of
+/// the lower header but different from it. As a chain has at most one block
+/// per height, both headers are on different forks.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize, Deserialize, Serialize)]
+pub enum AncestryProof {
+    /// The block is the parent of the higher header, i.e. its hash is the
+    /// `prev_block_hash` of the higher header.
+    PrevBlock(LightClientBlockLite),
+    /// The hash of the block is included in the `block_merkle_root` of the
+    /// higher header.
+    BlockMerkleRoot {
+        block: LightClientBlockLite,
+        merkle_path: MerklePath,
+    },
+}
+
+impl AncestryProof {
+    ///
+    pub fn block(&self) -> &LightClientBlockLite {
+        match self {
+            Self::PrevBlock(block) => block,
+            Self::BlockMerkleRoot { block, .. } => block,
+        }
+    }
+
+    /// Returns `true` if the proof shows that `lower` is not an ancestor of
+    /// `higher`.
+    pub fn proves_fork(&self, lower: &NearHeader, higher: &NearHeader) -> bool {
+        let block = self.block();
+        let block_hash = block.current_block_hash();
+        if lower.height() >= higher.height()
+            || block.inner_lite.height != lower.light_client_block.inner_lite.height
+            || block_hash == lower.light_client_block.current_block_hash()
+        {
+            return false;
+        }
+
+        match self {
+            Self::PrevBlock(_) => block_hash == higher.light_client_block.prev_block_hash,
+            Self::BlockMerkleRoot { merkle_path, .. } => verify_hash(
+                higher.light_client_block.inner_lite.block_merkle_root,
+                merkle_path,
+                block_hash,
+            ),
+        }
+    }
 }
 
 impl Misbehaviour {
+    /// Creates a misbehaviour, ordering the headers so that `header1` is the
+    /// one at the greater height.
     pub fn new(client_id: ClientId, header1: NearHeader, header2: NearHeader) -> Self {
+        let (header1, header2) = if header1.height() < header2.height() {
+            (header2, header1)
+        } else {
+            (header1, header2)
+        };
         Self {
             client_id,
             header1,
             header2,
+            ancestry_proof: None,
+        }
+    }
+
+    ///
+    pub fn with_ancestry_proof(self, ancestry_proof: AncestryProof) -> Self {
+        Self {
+            ancestry_proof: Some(ancestry_proof),
+            ..self
         }
     }
 
@@ -37,6 +130,126 @@ impl Misbehaviour {
     pub fn header2(&self) -> &NearHeader {
         &self.header2
     }
+
+    pub fn ancestry_proof(&self) -> Option<&AncestryProof> {
+        self.ancestry_proof.as_ref()
+    }
+
+    /// Returns what the headers disagree on, or `None` if they are no
+    /// evidence of misbehaviour.
+    pub fn kind(&self) -> Option<MisbehaviourKind> {
+        let header_1 = &self.header1;
+        let header_2 = &self.header2;
+
+        // Conflicting commitments to the block producers of the same next
+        // epoch are evidence of misbehaviour, whatever the heights and
+        // timestamps of the headers.
+        if header_1.has_conflicting_next_bps(header_2) {
+            return Some(MisbehaviourKind::ConflictingEpochs);
+        }
+
+        if header_1.height() == header_2.height() {
+            // when the height of the 2 headers are equal, we only have evidence
+            // of misbehaviour in the case where the headers are different
+            // (otherwise, the same header was added twice in the message,
+            // and this is evidence of nothing)
+            return (header_1.light_client_block.current_block_hash()
+                != header_2.light_client_block.current_block_hash())
+            .then_some(MisbehaviourKind::ConflictingBlocks);
+        }
+
+        // a block of the chain of header_1 at the height of header_2 which
+        // is not header_2 shows that both headers are on different forks,
+        // even if both advance normally.
+        if self
+            .ancestry_proof
+            .as_ref()
+            .is_some_and(|proof| proof.proves_fork(header_2, header_1))
+        {
+            return Some(MisbehaviourKind::Fork);
+        }
+
+        // header_1 is at greater height than header_2, therefore
+        // header_1 time must be less than or equal to
+        // header_2 time in order to be valid misbehaviour (violation of
+        // monotonic time).
+        (header_1.timestamp() <= header_2.timestamp())
+            .then_some(MisbehaviourKind::ConflictingTimestamps)
+    }
+
+    /// Checks that the ancestry proof of the misbehaviour, if any, shows that
+    /// `header2` is not an ancestor of `header1`.
+    ///
+    /// Headers at the same height have no ancestry to disprove, so the proof
+    /// is not checked for them.
+    pub fn verify_ancestry_proof(&self) -> Result<(), Error> {
+        match &self.ancestry_proof {
+            Some(proof)
+                if self.header1.height() != self.header2.height()
+                    && !proof.proves_fork(&self.header2, &self.header1) =>
+            {
+                Err(Error::InvalidAncestryProof)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the block producers which signed the approval messages of both
+    /// conflicting headers, given the block producers of the epoch of each
+    /// header.
+    pub fn equivocation_evidence(
+        &self,
+        header1_bps: &[ValidatorStakeView],
+        header2_bps: &[ValidatorStakeView],
+    ) -> Result<EquivocationEvidence, Error> {
+        if self.header1.height() != self.header2.height() {
+            return Err(Error::MisbehaviourHeadersNotAtSameHeight);
+        }
+        if self.header1.light_client_block.current_block_hash()
+            == self.header2.light_client_block.current_block_hash()
+        {
+            return Err(Error::MisbehaviourHeadersBlockHashesEqual);
+        }
+
+        let header2_approvers = self.header2.light_client_block.approvers(header2_bps);
+        let equivocators = self
+            .header1
+            .light_client_block
+            .approvers(header1_bps)
+            .into_iter()
+            .filter(|approver| {
+                header2_approvers.iter().any(|other| {
+                    other.account_id == approver.account_id
+                        && other.public_key == approver.public_key
+                })
+            })
+            .collect();
+        let total_stake = header1_bps
+            .iter()
+            .map(|bp| bp.clone().into_validator_stake().stake)
+            .sum();
+
+        Ok(EquivocationEvidence {
+            equivocators,
+            total_stake,
+        })
+    }
+}
+
+/// The block producers which approved both blocks of a misbehaviour.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EquivocationEvidence {
+    /// Block producers with valid signatures on both approval messages
+    pub equivocators: Vec<ValidatorStakeViewV1>,
+    /// Total stake of the block producers of the epoch of the first header
+    pub total_stake: Balance,
+}
+
+impl EquivocationEvidence {
+    /// Returns the stake of all equivocating block producers.
+    pub fn equivocating_stake(&self) -> Balance {
+        self.equivocators.iter().map(|bp| bp.stake).sum()
+    }
 }
 
 impl Protobuf<RawMisbehaviour> for Misbehaviour {}
@@ -65,7 +278,16 @@ impl TryFrom<RawMisbehaviour> for Misbehaviour {
             })?
             .try_into()?;
 
-        Ok(Self::new(client_id, header1, header2))
+        let misbehaviour = Self::new(client_id, header1, header2);
+        if raw.ancestry_proof.is_empty() {
+            return Ok(misbehaviour);
+        }
+        let ancestry_proof = AncestryProof::try_from_slice(&raw.ancestry_proof).map_err(|e| {
+            Error::InvalidRawMisbehaviour {
+                reason: format!("invalid ancestry proof: {:?}", e),
+            }
+        })?;
+        Ok(misbehaviour.with_ancestry_proof(ancestry_proof))
     }
 }
 
@@ -76,6 +298,11 @@ impl From<Misbehaviour> for RawMisbehaviour {
             client_id: value.client_id.to_string(),
             header_1: Some(value.header1.into()),
             header_2: Some(value.header2.into()),
+            ancestry_proof: value
+                .ancestry_proof
+                .as_ref()
+                .map(|proof| to_vec(proof).expect("never failed"))
+                .unwrap_or_default(),
         }
     }
 }
@@ -4,6 +4,7 @@ pub mod error;
 pub mod header;
 pub mod misbehaviour;
 pub mod near_types;
+pub mod state_proof;
 
 use ibc_core::host::types::identifiers::ClientType;
 
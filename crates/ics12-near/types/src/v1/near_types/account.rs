@@ -0,0 +1,169 @@
+//! Types of NEAR accounts and access keys.
+
+use super::super::error::Error;
+use super::{
+    hash::CryptoHash,
+    signature::PublicKey,
+    transaction::Nonce,
+    trie::{verify_not_in_state, verify_state_proof, ProofMode, RawTrieNodeWithSize},
+    AccountId, Balance, ACCESS_KEY, ACCESS_KEY_SEPARATOR, ACCOUNT, CONTRACT_CODE,
+};
+use alloc::{string::String, vec::Vec};
+use borsh::{to_vec, BorshDeserialize, BorshSerialize};
+
+pub type StorageUsage = u64;
+
+/// Per account information stored in the state.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct Account {
+    /// The total not locked tokens.
+    pub amount: Balance,
+    /// The amount locked due to staking.
+    pub locked: Balance,
+    /// Hash of the code stored in the storage for this account.
+    pub code_hash: CryptoHash,
+    /// Storage used by the given account, includes account id, this struct, access keys and other data.
+    pub storage_usage: StorageUsage,
+}
+
+impl Account {
+    ///
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Self::try_from_slice(bytes).map_err(|_| Error::BorshDeserializeError)
+    }
+}
+
+/// Access key of an account, allowing to sign transactions on its behalf.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct AccessKey {
+    /// Nonce of the latest transaction signed with the key.
+    pub nonce: Nonce,
+    /// What the key is allowed to do.
+    pub permission: AccessKeyPermission,
+}
+
+/// Permission of an access key.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub enum AccessKeyPermission {
+    FunctionCall(FunctionCallPermission),
+    /// Grants full access to the account.
+    FullAccess,
+}
+
+/// Permission to call methods of a single contract, without deposit.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct FunctionCallPermission {
+    /// Amount of tokens the key may spend on gas, `None` if unlimited.
+    pub allowance: Option<Balance>,
+    /// Account id of the contract the key may call.
+    pub receiver_id: AccountId,
+    /// Methods the key may call, any method of the contract if empty.
+    pub method_names: Vec<String>,
+}
+
+impl AccessKey {
+    ///
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Self::try_from_slice(bytes).map_err(|_| Error::BorshDeserializeError)
+    }
+}
+
+/// Returns the trie key of the account `account_id`.
+pub fn account_key(account_id: &AccountId) -> Vec<u8> {
+    let mut res = Vec::with_capacity(1 + account_id.len());
+    res.push(ACCOUNT);
+    res.extend(account_id.as_bytes());
+    res
+}
+
+/// Returns the trie key of the contract code of the account `account_id`.
+pub fn contract_code_key(account_id: &AccountId) -> Vec<u8> {
+    let mut res = Vec::with_capacity(1 + account_id.len());
+    res.push(CONTRACT_CODE);
+    res.extend(account_id.as_bytes());
+    res
+}
+
+/// Returns the trie key of the access key `public_key` of the account
+/// `account_id`.
+pub fn access_key_key(account_id: &AccountId, public_key: &PublicKey) -> Vec<u8> {
+    let public_key = to_vec(public_key).expect("never failed");
+    let mut res = Vec::with_capacity(1 + account_id.len() + 1 + public_key.len());
+    res.push(ACCESS_KEY);
+    res.extend(account_id.as_bytes());
+    res.push(ACCESS_KEY_SEPARATOR);
+    res.extend(public_key);
+    res
+}
+
+fn verify_value(
+    key: &[u8],
+    value: Option<&[u8]>,
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<(), Error> {
+    match value {
+        Some(value) => verify_state_proof(key, nodes, value, state_root, mode),
+        None => verify_not_in_state(key, nodes, state_root, mode),
+    }
+}
+
+/// Verifies that the account `account_id` is `account` in the state under
+/// `state_root`, or does not exist if `account` is `None`.
+pub fn verify_account(
+    account_id: &AccountId,
+    account: Option<&Account>,
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<(), Error> {
+    let account = account.map(|account| to_vec(account).expect("never failed"));
+    verify_value(
+        &account_key(account_id),
+        account.as_deref(),
+        nodes,
+        state_root,
+        mode,
+    )
+}
+
+/// Verifies that the access key `public_key` of the account `account_id` is
+/// `access_key` in the state under `state_root`, or does not exist if
+/// `access_key` is `None`.
+pub fn verify_access_key(
+    account_id: &AccountId,
+    public_key: &PublicKey,
+    access_key: Option<&AccessKey>,
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<(), Error> {
+    let access_key = access_key.map(|access_key| to_vec(access_key).expect("never failed"));
+    verify_value(
+        &access_key_key(account_id, public_key),
+        access_key.as_deref(),
+        nodes,
+        state_root,
+        mode,
+    )
+}
+
+/// Verifies that the contract code of the account `account_id` is `code` in
+/// the state under `state_root`, or that the account has no contract if
+/// `code` is `None`.
+pub fn verify_contract_code(
+    account_id: &AccountId,
+    code: Option<&[u8]>,
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<(), Error> {
+    verify_value(
+        &contract_code_key(account_id),
+        code,
+        nodes,
+        state_root,
+        mode,
+    )
+}
@@ -0,0 +1,50 @@
+//! Events emitted by NEAR contracts as logs, following NEP-297.
+
+use super::super::error::Error;
+use alloc::{format, string::String, string::ToString};
+use serde::Deserialize;
+
+/// Prefix of the logs holding a NEP-297 event.
+pub const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+/// A NEP-297 event, parsed from an `EVENT_JSON:` log.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Nep297Event {
+    /// Name of the standard the event follows, e.g. `nep171`
+    pub standard: String,
+    /// Version of the standard
+    pub version: String,
+    /// Type of the event, e.g. `nft_mint`
+    pub event: String,
+    /// The whole event as logged, i.e. the JSON object following the prefix,
+    /// including its `data` field
+    pub json: String,
+}
+
+#[derive(Deserialize)]
+struct RawNep297Event {
+    standard: String,
+    version: String,
+    event: String,
+}
+
+impl Nep297Event {
+    /// Parses an `EVENT_JSON:` log line.
+    pub fn parse(log: &str) -> Result<Self, Error> {
+        let json = log
+            .strip_prefix(EVENT_JSON_PREFIX)
+            .ok_or_else(|| Error::InvalidEventLog {
+                reason: format!("log does not start with `{}`", EVENT_JSON_PREFIX),
+            })?;
+        let raw: RawNep297Event =
+            serde_json::from_str(json).map_err(|e| Error::InvalidEventLog {
+                reason: e.to_string(),
+            })?;
+        Ok(Self {
+            standard: raw.standard,
+            version: raw.version,
+            event: raw.event,
+            json: json.to_string(),
+        })
+    }
+}
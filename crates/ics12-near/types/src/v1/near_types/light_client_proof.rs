@@ -0,0 +1,210 @@
+//! Proofs of the execution outcomes of transactions and receipts, as returned
+//! by the `light_client_proof` RPC method of NEAR.
+
+use super::super::error::Error;
+use super::{
+    event::Nep297Event,
+    hash::CryptoHash,
+    merkle::{compute_root_from_path, MerklePath},
+    transaction::{ExecutionOutcomeWithId, Transaction},
+    AccountId, LightClientBlockLite,
+};
+use alloc::format;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Execution outcome with the merkle path of its hash to the outcome root of
+/// its shard.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ExecutionOutcomeWithIdAndProof {
+    pub proof: MerklePath,
+    /// Hash of the block the outcome was included in
+    pub block_hash: CryptoHash,
+    pub outcome_with_id: ExecutionOutcomeWithId,
+}
+
+/// Proof that an execution outcome was included in a block, itself included
+/// in the `block_merkle_root` of a later block.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ExecutionOutcomeProof {
+    pub outcome_proof: ExecutionOutcomeWithIdAndProof,
+    /// Merkle path of the outcome root of the shard to the outcome root of
+    /// the block
+    pub outcome_root_proof: MerklePath,
+    /// Block the outcome was included in
+    pub block_header_lite: LightClientBlockLite,
+    /// Merkle path of the hash of the block to the `block_merkle_root` of the
+    /// later block
+    pub block_proof: MerklePath,
+}
+
+impl ExecutionOutcomeProof {
+    ///
+    pub fn outcome_with_id(&self) -> &ExecutionOutcomeWithId {
+        &self.outcome_proof.outcome_with_id
+    }
+
+    /// Verifies that the outcome was included in a block whose hash is
+    /// included in `block_merkle_root`, the one of a trusted block.
+    ///
+    /// As the `block_merkle_root` of a block covers the blocks before it, the
+    /// outcome must be in a block lower than the trusted block.
+    pub fn verify(&self, block_merkle_root: &CryptoHash) -> Result<(), Error> {
+        let outcome_hash = CryptoHash::hash_borsh(&self.outcome_with_id().to_hashes());
+        let shard_outcome_root = compute_root_from_path(&self.outcome_proof.proof, outcome_hash);
+        let block_outcome_root = compute_root_from_path(
+            &self.outcome_root_proof,
+            CryptoHash::hash_borsh(&shard_outcome_root),
+        );
+        if block_outcome_root != self.block_header_lite.inner_lite.outcome_root {
+            return Err(Error::InvalidOutcomeProof);
+        }
+
+        let block_hash = self.block_header_lite.current_block_hash();
+        if block_hash != self.outcome_proof.block_hash {
+            return Err(Error::InvalidOutcomeBlockHash);
+        }
+        if compute_root_from_path(&self.block_proof, block_hash) != *block_merkle_root {
+            return Err(Error::InvalidBlockProof);
+        }
+        Ok(())
+    }
+
+    /// Verifies the proof as [`Self::verify`] does, and returns the NEP-297
+    /// event of the log at `log_index`, which must have been emitted by
+    /// `executor_id`.
+    pub fn verify_event(
+        &self,
+        block_merkle_root: &CryptoHash,
+        executor_id: &AccountId,
+        log_index: usize,
+    ) -> Result<Nep297Event, Error> {
+        self.verify(block_merkle_root)?;
+        let outcome = &self.outcome_with_id().outcome;
+        if outcome.executor_id != *executor_id {
+            return Err(Error::MismatchOutcomeExecutorId {
+                expected: executor_id.clone(),
+                actual: outcome.executor_id.clone(),
+            });
+        }
+        let log = outcome
+            .logs
+            .get(log_index)
+            .ok_or(Error::MissingOutcomeLog { index: log_index })?;
+        Nep297Event::parse(log)
+    }
+}
+
+/// Proof of the contents of an executed transaction.
+///
+/// The id of the execution outcome of a transaction is the hash of the
+/// transaction, which binds all its contents, including its actions.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct TransactionProof {
+    pub transaction: Transaction,
+    /// Proof of the execution outcome of the transaction
+    pub outcome_proof: ExecutionOutcomeProof,
+}
+
+impl TransactionProof {
+    /// Verifies that the transaction was executed in a block whose hash is
+    /// included in `block_merkle_root`, the one of a trusted block.
+    pub fn verify(&self, block_merkle_root: &CryptoHash) -> Result<(), Error> {
+        self.outcome_proof.verify(block_merkle_root)?;
+        let outcome_with_id = self.outcome_proof.outcome_with_id();
+        if outcome_with_id.id != self.transaction.get_hash() {
+            return Err(Error::InvalidTransactionProof {
+                reason: "outcome id is not the hash of the transaction".into(),
+            });
+        }
+        if outcome_with_id.outcome.executor_id != self.transaction.signer_id {
+            return Err(Error::InvalidTransactionProof {
+                reason: format!(
+                    "outcome executed by {} instead of the signer {}",
+                    outcome_with_id.outcome.executor_id, self.transaction.signer_id
+                ),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Proof of an executed receipt, linked to the execution outcome of the
+/// transaction or receipt which created it.
+///
+/// Unlike transactions, receipts are not committed to in the data available
+/// to light clients: the id of a receipt is not a hash of its contents. The
+/// proof therefore only establishes the facts of a [`VerifiedReceipt`], taken
+/// from the execution outcomes, and not the body of the receipt, such as its
+/// predecessor or its actions.
+///
+/// The actions of a receipt created by a transaction are the ones of the
+/// transaction: verify a [`TransactionProof`] whose outcome id is the
+/// `parent_outcome_id` of the receipt to learn them.
+#[derive(Clone, Debug, PartialEq, BorshSerialize, BorshDeserialize)]
+pub struct ReceiptProof {
+    /// Id of the receipt
+    pub receipt_id: CryptoHash,
+    /// Proof of the execution outcome of the transaction or receipt which
+    /// created the receipt
+    pub parent_outcome_proof: ExecutionOutcomeProof,
+    /// Proof of the execution outcome of the receipt
+    pub outcome_proof: ExecutionOutcomeProof,
+}
+
+/// Facts about a receipt established by a [`ReceiptProof`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VerifiedReceipt {
+    ///
+    pub receipt_id: CryptoHash,
+    /// Account id of the executor of the outcome which created the receipt.
+    ///
+    /// It is usually the predecessor of the receipt, but not always: the
+    /// predecessor of a refund is `system`, whatever account created it. The
+    /// predecessor itself is part of the receipt body, which the proof does
+    /// not establish.
+    pub creator_id: AccountId,
+    /// Account id of the receiver of the receipt, the executor of its outcome
+    pub receiver_id: AccountId,
+    /// Id of the outcome which created the receipt, i.e. the hash of the
+    /// transaction or the id of the receipt it was created by
+    pub parent_outcome_id: CryptoHash,
+}
+
+impl ReceiptProof {
+    /// Verifies that the receipt was created and executed in blocks whose
+    /// hashes are included in `block_merkle_root`, the one of a trusted block,
+    /// and returns what the proof establishes about the receipt.
+    pub fn verify(&self, block_merkle_root: &CryptoHash) -> Result<VerifiedReceipt, Error> {
+        self.parent_outcome_proof.verify(block_merkle_root)?;
+        self.outcome_proof.verify(block_merkle_root)?;
+
+        let receipt_id = self.receipt_id;
+        let parent_outcome_with_id = self.parent_outcome_proof.outcome_with_id();
+        if !parent_outcome_with_id
+            .outcome
+            .receipt_ids
+            .contains(&receipt_id)
+        {
+            return Err(Error::InvalidReceiptProof {
+                reason: format!(
+                    "receipt {} is not created by the parent outcome",
+                    receipt_id
+                ),
+            });
+        }
+
+        let outcome_with_id = self.outcome_proof.outcome_with_id();
+        if outcome_with_id.id != receipt_id {
+            return Err(Error::InvalidReceiptProof {
+                reason: format!("outcome is not the one of receipt {}", receipt_id),
+            });
+        }
+
+        Ok(VerifiedReceipt {
+            receipt_id,
+            creator_id: parent_outcome_with_id.outcome.executor_id.clone(),
+            receiver_id: outcome_with_id.outcome.executor_id.clone(),
+            parent_outcome_id: parent_outcome_with_id.id,
+        })
+    }
+}
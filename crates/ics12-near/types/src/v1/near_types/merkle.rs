@@ -2,17 +2,18 @@ use super::hash::{combine_hash, CryptoHash};
 use alloc::vec;
 use alloc::vec::Vec;
 use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
 
 /// Hash used by a struct implementing the Merkle tree.
 pub type MerkleHash = CryptoHash;
 
-#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, Deserialize, Serialize)]
 pub enum Direction {
     Left,
     Right,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, Deserialize, Serialize)]
 pub struct MerklePathItem {
     pub hash: MerkleHash,
     pub direction: Direction,
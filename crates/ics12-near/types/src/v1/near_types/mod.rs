@@ -3,7 +3,10 @@
 //! Most of the codes in this module are ported from `nearcore` v1.30.0
 //! and are applied by necessary changes to remove std dependencies.
 
+pub mod account;
+pub mod event;
 pub mod hash;
+pub mod light_client_proof;
 pub mod merkle;
 pub mod signature;
 pub mod transaction;
@@ -18,9 +21,19 @@ use borsh::to_vec;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 
+/// This column id is used when storing `account::Account` type about a given
+/// `account_id`.
+pub const ACCOUNT: u8 = 0;
+/// This column id is used when storing contract blob for a given `account_id`.
+pub const CONTRACT_CODE: u8 = 1;
+/// This column id is used when storing `account::AccessKey` type for a given
+/// `account_id`.
+pub const ACCESS_KEY: u8 = 2;
 /// This column id is used when storing Key-Value data from a contract on an `account_id`.
 pub const CONTRACT_DATA: u8 = 9;
 pub const ACCOUNT_DATA_SEPARATOR: u8 = b',';
+/// Separates the account id from the public key in the keys of the `ACCESS_KEY` column.
+pub const ACCESS_KEY_SEPARATOR: u8 = ACCESS_KEY;
 
 pub type BlockHeight = u64;
 pub type AccountId = String;
@@ -137,6 +150,24 @@ impl LightClientBlock {
         ]
         .concat()
     }
+    /// Returns the block producers, out of the `block_producers` of the epoch of
+    /// this block, which have a valid signature on its approval message.
+    pub fn approvers(&self, block_producers: &[ValidatorStakeView]) -> Vec<ValidatorStakeViewV1> {
+        let approval_message = self.approval_message();
+        self.approvals_after_next
+            .iter()
+            .zip(block_producers.iter())
+            .filter_map(|(maybe_signature, block_producer)| {
+                let block_producer = block_producer.clone().into_validator_stake();
+                maybe_signature
+                    .as_ref()
+                    .filter(|signature| {
+                        signature.verify(&approval_message, &block_producer.public_key)
+                    })
+                    .map(|_| block_producer)
+            })
+            .collect()
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
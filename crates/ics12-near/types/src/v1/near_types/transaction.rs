@@ -2,12 +2,102 @@ use super::CryptoHash;
 use alloc::{string::String, vec::Vec};
 use borsh::{BorshDeserialize, BorshSerialize};
 
-use super::{AccountId, Balance};
+use super::{account::AccessKey, signature::PublicKey, AccountId, Balance};
 
 pub type Nonce = u64;
 pub type LogEntry = String;
 pub type Gas = u64;
 
+/// Transaction, as signed by the signer account.
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+pub struct Transaction {
+    /// An account on which behalf transaction is signed
+    pub signer_id: AccountId,
+    /// A public key of the access key which was used to sign an account.
+    pub public_key: PublicKey,
+    /// Nonce is used to determine order of transaction in the pool.
+    pub nonce: Nonce,
+    /// Receiver account for this transaction
+    pub receiver_id: AccountId,
+    /// The hash of the block in the blockchain on top of which the given transaction is valid
+    pub block_hash: CryptoHash,
+    /// A list of actions to be applied
+    pub actions: Vec<Action>,
+}
+
+impl Transaction {
+    /// Returns the hash of the transaction, which is the id of its execution
+    /// outcome.
+    pub fn get_hash(&self) -> CryptoHash {
+        CryptoHash::hash_borsh(self)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+pub enum Action {
+    /// Create an (sub)account using a transaction `receiver_id` as an ID for
+    /// a new account
+    CreateAccount(CreateAccountAction),
+    DeployContract(DeployContractAction),
+    FunctionCall(FunctionCallAction),
+    Transfer(TransferAction),
+    Stake(StakeAction),
+    AddKey(AddKeyAction),
+    DeleteKey(DeleteKeyAction),
+    DeleteAccount(DeleteAccountAction),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct CreateAccountAction {}
+
+/// Deploy contract action
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct DeployContractAction {
+    /// WebAssembly binary
+    pub code: Vec<u8>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct FunctionCallAction {
+    pub method_name: String,
+    pub args: Vec<u8>,
+    pub gas: Gas,
+    pub deposit: Balance,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct TransferAction {
+    pub deposit: Balance,
+}
+
+/// An action which stakes signer_id tokens and setup's validator public key
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+pub struct StakeAction {
+    /// Amount of tokens to stake.
+    pub stake: Balance,
+    /// Validator key which will be used to sign transactions on behalf of singer_id
+    pub public_key: PublicKey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+pub struct AddKeyAction {
+    /// A public key which will be associated with an access_key
+    pub public_key: PublicKey,
+    /// An access key with the permission
+    pub access_key: AccessKey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Clone, Debug)]
+pub struct DeleteKeyAction {
+    /// A public key associated with the access_key to be deleted.
+    pub public_key: PublicKey,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
+pub struct DeleteAccountAction {
+    pub beneficiary_id: AccountId,
+}
+
 /// The status of execution for a transaction or a receipt.
 #[derive(BorshSerialize, BorshDeserialize, PartialEq, Eq, Clone, Debug)]
 pub enum ExecutionStatus {
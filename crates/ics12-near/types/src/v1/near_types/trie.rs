@@ -1,19 +1,23 @@
+#[cfg(feature = "std")]
+pub mod builder;
+pub mod ics23;
 pub mod nibble_slice;
 
 use self::nibble_slice::NibbleSlice;
 use super::super::error::Error as StateProofVerificationError;
 use super::{hash::sha256, CryptoHash};
-use alloc::{vec, vec::Vec};
+use alloc::{collections::BTreeMap, format, vec, vec::Vec};
 use borsh::io::{Error, ErrorKind, Read};
 use byteorder::{ByteOrder, LittleEndian};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct RawTrieNodeWithSize {
     node: RawTrieNode,
     memory_usage: u64,
 }
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 #[allow(clippy::large_enum_variant)]
 pub enum RawTrieNode {
     Leaf(Vec<u8>, u32, CryptoHash),
@@ -44,6 +48,37 @@ fn decode_children(bytes: &[u8]) -> Result<[Option<CryptoHash>; 16], Error> {
     Ok(children)
 }
 
+/// Reads the key of a leaf or extension node, which is never empty as it
+/// holds at least the flags of the encoded nibbles.
+///
+/// The key length is checked against the remaining bytes before allocating
+/// the key.
+fn decode_key(cursor: &mut &[u8]) -> Result<Vec<u8>, Error> {
+    let mut four_bytes: [u8; 4] = [0; 4];
+    cursor.read_exact(&mut four_bytes)?;
+    let key_length = LittleEndian::read_u32(&four_bytes) as usize;
+    if key_length == 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "Empty key"));
+    }
+    if key_length > cursor.len() {
+        return Err(Error::new(ErrorKind::UnexpectedEof, "Key out of bounds"));
+    }
+    let mut key = vec![0; key_length];
+    cursor.read_exact(&mut key)?;
+    Ok(key)
+}
+
+/// Returns the length of the key of an encoded leaf or extension node, `None`
+/// for other nodes or if the length cannot be read.
+fn encoded_key_length(bytes: &[u8]) -> Option<usize> {
+    match bytes.first() {
+        Some(&LEAF_NODE) | Some(&EXTENSION_NODE) => bytes
+            .get(1..5)
+            .map(|length| LittleEndian::read_u32(length) as usize),
+        _ => None,
+    }
+}
+
 impl RawTrieNode {
     fn encode_into(&self, out: &mut Vec<u8>) {
         // size in state_parts = size + 8 for RawTrieNodeWithSize + 8 for borsh vector length
@@ -94,11 +129,7 @@ impl RawTrieNode {
         cursor.read_exact(&mut one_byte)?;
         match one_byte[0] {
             LEAF_NODE => {
-                let mut four_bytes: [u8; 4] = [0; 4];
-                cursor.read_exact(&mut four_bytes)?;
-                let key_length = LittleEndian::read_u32(&four_bytes);
-                let mut key = vec![0; key_length as usize];
-                cursor.read_exact(&mut key)?;
+                let key = decode_key(&mut cursor)?;
                 let mut four_bytes: [u8; 4] = [0; 4];
                 cursor.read_exact(&mut four_bytes)?;
                 let value_length = LittleEndian::read_u32(&four_bytes);
@@ -125,11 +156,7 @@ impl RawTrieNode {
                 ))
             }
             EXTENSION_NODE => {
-                let mut four_bytes: [u8; 4] = [0; 4];
-                cursor.read_exact(&mut four_bytes)?;
-                let key_length = LittleEndian::read_u32(&four_bytes);
-                let mut key = vec![0; key_length as usize];
-                cursor.read_exact(&mut key)?;
+                let key = decode_key(&mut cursor)?;
                 let mut child = [0; 32];
                 cursor.read_exact(&mut child)?;
                 Ok(RawTrieNode::Extension(key, CryptoHash(child)))
@@ -145,6 +172,31 @@ impl RawTrieNodeWithSize {
         out.extend(self.memory_usage.to_le_bytes());
     }
 
+    /// Returns the node, without its memory usage.
+    pub fn node(&self) -> &RawTrieNode {
+        &self.node
+    }
+
+    /// Returns the encoding of the node, as stored in the NEAR state.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        self.encode_into(&mut out);
+        out
+    }
+
+    /// Returns the hash of the node, which is the key it is referred to with
+    /// by its parent.
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash(sha256(&self.encode()))
+    }
+
+    /// Returns the hash of the node, using `buf` to encode it.
+    fn hash_into(&self, buf: &mut Vec<u8>) -> CryptoHash {
+        buf.clear();
+        self.encode_into(buf);
+        CryptoHash(sha256(buf))
+    }
+
     pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
         if bytes.len() < 8 {
             return Err(Error::new(ErrorKind::Other, "Wrong type"));
@@ -155,94 +207,217 @@ impl RawTrieNodeWithSize {
         let memory_usage = u64::from_le_bytes(arr);
         Ok(RawTrieNodeWithSize { node, memory_usage })
     }
+
+    /// Decodes the node at `proof_index` of a proof, rejecting a key longer
+    /// than allowed by `limits` before decoding it.
+    pub fn decode_with_limits(
+        bytes: &[u8],
+        limits: &ProofLimits,
+        proof_index: u16,
+    ) -> Result<Self, StateProofVerificationError> {
+        if let Some(length) = encoded_key_length(bytes) {
+            if length > limits.max_key_length {
+                return Err(StateProofVerificationError::ProofNodeKeyTooLong {
+                    proof_index,
+                    length,
+                    max: limits.max_key_length,
+                });
+            }
+        }
+        Self::decode(bytes)
+            .map_err(|_| StateProofVerificationError::InvalidProofData { proof_index })
+    }
 }
 
-pub fn verify_state_proof(
+/// Maximum size of the untrusted proofs accepted, so that a proof can not make
+/// the verifier allocate or traverse without bound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProofLimits {
+    /// Maximum number of trie nodes in a proof
+    pub max_nodes: usize,
+    /// Maximum size of an encoded proof in bytes
+    pub max_total_bytes: usize,
+    /// Maximum length in bytes of the encoded key of a leaf or extension node
+    pub max_key_length: usize,
+    /// Maximum length in nibbles of a key looked up in a proof
+    pub max_nibble_depth: usize,
+}
+
+impl ProofLimits {
+    /// Default maximum number of trie nodes, far above the few dozens of nodes
+    /// of the proof of a key in a NEAR shard.
+    pub const DEFAULT_MAX_NODES: usize = 1024;
+    /// Default maximum size of a proof, enough for the default maximum number
+    /// of branch nodes with all their children.
+    pub const DEFAULT_MAX_TOTAL_BYTES: usize = 1 << 20;
+    /// Default maximum length of a node key, twice the maximum length of a
+    /// storage key in NEAR to leave room for the prefix of trie keys.
+    pub const DEFAULT_MAX_KEY_LENGTH: usize = 4096;
+    /// Default maximum nibble depth, the number of nibbles of a key of the
+    /// default maximum length.
+    pub const DEFAULT_MAX_NIBBLE_DEPTH: usize = 2 * Self::DEFAULT_MAX_KEY_LENGTH;
+    /// Upper bound of `max_nodes`, as the nodes of a proof are indexed with a
+    /// `u16`.
+    pub const MAX_NODES: usize = u16::MAX as usize + 1;
+
+    /// Checks that the limits can be enforced.
+    pub fn validate(&self) -> Result<(), StateProofVerificationError> {
+        match self.max_nodes > Self::MAX_NODES {
+            true => Err(StateProofVerificationError::InvalidProofLimits {
+                reason: format!(
+                    "maximum number of nodes {} exceeds {}",
+                    self.max_nodes,
+                    Self::MAX_NODES
+                ),
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks that `nodes`, the number of nodes of a proof, is allowed.
+    ///
+    /// A proof is never allowed more than [`Self::MAX_NODES`] nodes, even with
+    /// limits which do not [`validate`](Self::validate).
+    pub fn check_node_count(&self, nodes: usize) -> Result<(), StateProofVerificationError> {
+        let max = self.max_nodes.min(Self::MAX_NODES);
+        match nodes > max {
+            true => Err(StateProofVerificationError::TooManyProofNodes { count: nodes, max }),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks that `size`, the size of an encoded proof, is allowed.
+    pub fn check_total_bytes(&self, size: usize) -> Result<(), StateProofVerificationError> {
+        match size > self.max_total_bytes {
+            true => Err(StateProofVerificationError::ProofTooLarge {
+                size,
+                max: self.max_total_bytes,
+            }),
+            false => Ok(()),
+        }
+    }
+
+    /// Checks that the nibbles of `key` do not exceed the maximum depth.
+    pub fn check_key(&self, key: &[u8]) -> Result<(), StateProofVerificationError> {
+        self.check_nibble_depth(key.len().saturating_mul(2))
+    }
+
+    /// Checks that `depth`, the length in nibbles of a path in the trie, does
+    /// not exceed the maximum depth.
+    pub fn check_nibble_depth(&self, depth: usize) -> Result<(), StateProofVerificationError> {
+        match depth > self.max_nibble_depth {
+            true => Err(StateProofVerificationError::ProofKeyTooDeep {
+                depth,
+                max: self.max_nibble_depth,
+            }),
+            false => Ok(()),
+        }
+    }
+}
+
+impl Default for ProofLimits {
+    fn default() -> Self {
+        Self {
+            max_nodes: Self::DEFAULT_MAX_NODES,
+            max_total_bytes: Self::DEFAULT_MAX_TOTAL_BYTES,
+            max_key_length: Self::DEFAULT_MAX_KEY_LENGTH,
+            max_nibble_depth: Self::DEFAULT_MAX_NIBBLE_DEPTH,
+        }
+    }
+}
+
+/// How strictly the nodes of a proof are checked.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProofMode {
+    /// Every node of the proof must be used to verify it, and appear only
+    /// once, so that a fact has a single valid proof.
+    #[default]
+    Strict,
+    /// Nodes which are not needed to verify the proof are ignored.
+    Lenient,
+}
+
+/// Reference to a value, as stored in the trie nodes instead of the value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValueRef {
+    /// Length of the value in bytes
+    pub length: u32,
+    /// SHA-256 hash of the value
+    pub hash: CryptoHash,
+}
+
+impl ValueRef {
+    /// Returns the reference to `value`.
+    pub fn of(value: &[u8]) -> Self {
+        Self {
+            length: value.len() as u32,
+            hash: CryptoHash(sha256(value)),
+        }
+    }
+}
+
+/// Where the lookup of a key in a trie ended.
+enum LookupResult {
+    /// The key has a value, held by the node at `proof_index`.
+    Value {
+        proof_index: u16,
+        value_ref: ValueRef,
+    },
+    /// The key has no value, as shown by the node at `proof_index`.
+    Absent { proof_index: u16 },
+}
+
+/// Looks `key` up in the trie under `state_root`.
+///
+/// `get_node` returns the node with the given hash, along with its index in
+/// the proof.
+fn lookup<'a, F>(
     key: &[u8],
-    nodes: &[RawTrieNodeWithSize],
-    value: &[u8],
     state_root: &CryptoHash,
-) -> Result<(), StateProofVerificationError> {
-    let mut v = Vec::new();
-    let mut hash_node = |node: &RawTrieNodeWithSize| {
-        v.clear();
-        node.encode_into(&mut v);
-        CryptoHash(sha256(&v))
-    };
+    mut get_node: F,
+) -> Result<LookupResult, StateProofVerificationError>
+where
+    F: FnMut(&CryptoHash) -> Result<(u16, &'a RawTrieNodeWithSize), StateProofVerificationError>,
+{
     let mut key = NibbleSlice::new(key);
     let mut expected_hash = *state_root;
 
-    for (node_index, node) in (0_u16..).zip(nodes.iter()) {
-        match node {
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Leaf(node_key, _, value_hash),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-
+    loop {
+        let (proof_index, node) = get_node(&expected_hash)?;
+        match &node.node {
+            RawTrieNode::Leaf(node_key, value_length, value_hash) => {
                 let nib = &NibbleSlice::from_encoded(node_key).0;
                 if &key != nib {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-
-                match CryptoHash(sha256(value)) == *value_hash {
-                    true => return Ok(()),
-                    false => {
-                        return Err(StateProofVerificationError::InvalidProofData {
-                            proof_index: node_index,
-                        })
-                    }
+                    return Ok(LookupResult::Absent { proof_index });
                 }
+                return Ok(LookupResult::Value {
+                    proof_index,
+                    value_ref: ValueRef {
+                        length: *value_length,
+                        hash: *value_hash,
+                    },
+                });
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Extension(node_key, child_hash),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-                expected_hash = *child_hash;
-
+            RawTrieNode::Extension(node_key, child_hash) => {
                 let nib = NibbleSlice::from_encoded(node_key).0;
                 if !key.starts_with(&nib) {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
+                    return Ok(LookupResult::Absent { proof_index });
                 }
+                expected_hash = *child_hash;
                 key = key.mid(nib.len());
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Branch(children, node_value),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-
+            RawTrieNode::Branch(children, node_value) => {
                 if key.is_empty() {
-                    let maybe_value_hash = node_value.map(|x| x.1);
-                    let expected_value_hash = CryptoHash(sha256(value));
-                    return match maybe_value_hash {
-                        Some(value_hash) => match expected_value_hash == value_hash {
-                            true => Ok(()),
-                            false => Err(StateProofVerificationError::InvalidProofData {
-                                proof_index: node_index,
-                            }),
+                    return Ok(match node_value {
+                        Some((value_length, value_hash)) => LookupResult::Value {
+                            proof_index,
+                            value_ref: ValueRef {
+                                length: *value_length,
+                                hash: *value_hash,
+                            },
                         },
-                        None => Err(StateProofVerificationError::InvalidProofData {
-                            proof_index: node_index,
-                        }),
-                    };
+                        None => LookupResult::Absent { proof_index },
+                    });
                 }
                 let index = key.at(0);
                 match &children[index as usize] {
@@ -250,99 +425,414 @@ pub fn verify_state_proof(
                         key = key.mid(1);
                         expected_hash = *child_hash;
                     }
-                    None => {
-                        return Err(StateProofVerificationError::InvalidProofData {
-                            proof_index: node_index,
-                        })
-                    }
+                    None => return Ok(LookupResult::Absent { proof_index }),
                 }
             }
         }
     }
-    Err(StateProofVerificationError::InvalidProofDataLength)
+}
+
+/// Returns the nodes on the path of `key` in the trie under `state_root`, in
+/// order from the root, as taken by `verify_state_proof` and
+/// `verify_not_in_state`.
+///
+/// `nodes` may be in any order and hold nodes which are not on the path.
+pub fn select_path_nodes(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+) -> Result<Vec<RawTrieNodeWithSize>, StateProofVerificationError> {
+    let nodes: BTreeMap<CryptoHash, &RawTrieNodeWithSize> =
+        nodes.iter().map(|node| (node.hash(), node)).collect();
+    let mut path = Vec::new();
+    lookup(key, state_root, |hash| {
+        let node = nodes
+            .get(hash)
+            .ok_or(StateProofVerificationError::MissingProofData)?;
+        path.push((*node).clone());
+        Ok((0, *node))
+    })?;
+    Ok(path)
+}
+
+/// Looks `key` up in the trie under `state_root`, with `nodes` being the
+/// nodes on the path of the key, in order from the root.
+fn lookup_in_path(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<LookupResult, StateProofVerificationError> {
+    let mut v = Vec::new();
+    let mut path = (0_u16..).zip(nodes.iter());
+    let lookup_result = lookup(key, state_root, |expected_hash| {
+        let (node_index, node) = path
+            .next()
+            .ok_or(StateProofVerificationError::InvalidProofDataLength)?;
+        if node.hash_into(&mut v) != *expected_hash {
+            return Err(StateProofVerificationError::InvalidProofData {
+                proof_index: node_index,
+            });
+        }
+        Ok((node_index, node))
+    })?;
+    if mode == ProofMode::Strict {
+        if let Some((proof_index, _)) = path.next() {
+            return Err(StateProofVerificationError::UnusedProofData { proof_index });
+        }
+    }
+    Ok(lookup_result)
+}
+
+fn check_value(
+    lookup_result: LookupResult,
+    value: Option<&[u8]>,
+) -> Result<(), StateProofVerificationError> {
+    match (lookup_result, value) {
+        (
+            LookupResult::Value {
+                proof_index,
+                value_ref,
+            },
+            Some(value),
+        ) => match CryptoHash(sha256(value)) == value_ref.hash {
+            true => Ok(()),
+            false => Err(StateProofVerificationError::InvalidProofData { proof_index }),
+        },
+        (LookupResult::Absent { proof_index }, Some(_)) => {
+            Err(StateProofVerificationError::InvalidProofData { proof_index })
+        }
+        (LookupResult::Value { .. }, None) => {
+            Err(StateProofVerificationError::SpecifiedKeyHasValueInState)
+        }
+        (LookupResult::Absent { .. }, None) => Ok(()),
+    }
+}
+
+pub fn verify_state_proof(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    value: &[u8],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<(), StateProofVerificationError> {
+    check_value(lookup_in_path(key, nodes, state_root, mode)?, Some(value))
 }
 
 pub fn verify_not_in_state(
     key: &[u8],
     nodes: &[RawTrieNodeWithSize],
     state_root: &CryptoHash,
+    mode: ProofMode,
 ) -> Result<(), StateProofVerificationError> {
-    let mut v = Vec::new();
-    let mut hash_node = |node: &RawTrieNodeWithSize| {
-        v.clear();
-        node.encode_into(&mut v);
-        CryptoHash(sha256(&v))
-    };
-    let mut key = NibbleSlice::new(key);
-    let mut expected_hash = *state_root;
+    check_value(lookup_in_path(key, nodes, state_root, mode)?, None)
+}
 
-    for (node_index, node) in (0_u16..).zip(nodes.iter()) {
-        match node {
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Leaf(node_key, _, _),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
+/// Verifies that `key` has the value referred to by `value_ref` in the trie
+/// under `state_root`, without the value itself.
+///
+/// This binds a large value to the state through its hash and length only.
+pub fn verify_state_proof_of_value_ref(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    value_ref: &ValueRef,
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<(), StateProofVerificationError> {
+    match lookup_in_path(key, nodes, state_root, mode)? {
+        LookupResult::Value {
+            proof_index,
+            value_ref: proven_value_ref,
+        } => match proven_value_ref == *value_ref {
+            true => Ok(()),
+            false => Err(StateProofVerificationError::InvalidProofData { proof_index }),
+        },
+        LookupResult::Absent { proof_index } => {
+            Err(StateProofVerificationError::InvalidProofData { proof_index })
+        }
+    }
+}
+
+/// Returns the reference to the value of `key` in the trie under
+/// `state_root`, as proven by `nodes`, or `None` if `key` has no value.
+///
+/// The length of the value is known from the proof alone, and the value can
+/// be checked later against the hash.
+pub fn get_value_ref(
+    key: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+) -> Result<Option<ValueRef>, StateProofVerificationError> {
+    Ok(match lookup_in_path(key, nodes, state_root, mode)? {
+        LookupResult::Value { value_ref, .. } => Some(value_ref),
+        LookupResult::Absent { .. } => None,
+    })
+}
+
+/// Nodes of a proof given as a set, indexed by hash.
+struct ProofNodes<'a> {
+    nodes_by_hash: BTreeMap<CryptoHash, (u16, &'a RawTrieNodeWithSize)>,
+    used: Vec<bool>,
+    mode: ProofMode,
+}
+
+impl<'a> ProofNodes<'a> {
+    fn new(
+        nodes: &'a [RawTrieNodeWithSize],
+        mode: ProofMode,
+    ) -> Result<Self, StateProofVerificationError> {
+        let mut v = Vec::new();
+        let mut nodes_by_hash = BTreeMap::new();
+        for (node_index, node) in (0_u16..).zip(nodes.iter()) {
+            let hash = node.hash_into(&mut v);
+            if nodes_by_hash.contains_key(&hash) {
+                if mode == ProofMode::Strict {
+                    return Err(StateProofVerificationError::DuplicateProofData {
                         proof_index: node_index,
                     });
                 }
+                continue;
+            }
+            nodes_by_hash.insert(hash, (node_index, node));
+        }
+        Ok(Self {
+            nodes_by_hash,
+            used: vec![false; nodes.len()],
+            mode,
+        })
+    }
 
-                let nib = &NibbleSlice::from_encoded(node_key).0;
-                if &key != nib {
-                    return Ok(());
-                }
+    /// Returns the node with the given hash, along with its index in the proof.
+    fn get(
+        &mut self,
+        hash: &CryptoHash,
+    ) -> Result<(u16, &'a RawTrieNodeWithSize), StateProofVerificationError> {
+        let (node_index, node) = self
+            .nodes_by_hash
+            .get(hash)
+            .copied()
+            .ok_or(StateProofVerificationError::MissingProofData)?;
+        self.used[usize::from(node_index)] = true;
+        Ok((node_index, node))
+    }
 
-                return Err(StateProofVerificationError::SpecifiedKeyHasValueInState);
+    /// Checks that every node was used, in strict mode.
+    fn finish(self) -> Result<(), StateProofVerificationError> {
+        if self.mode == ProofMode::Lenient {
+            return Ok(());
+        }
+        match (0_u16..).zip(self.used).find(|(_, used)| !used) {
+            Some((proof_index, _)) => {
+                Err(StateProofVerificationError::UnusedProofData { proof_index })
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Extension(node_key, child_hash),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
-                }
-                expected_hash = *child_hash;
+            None => Ok(()),
+        }
+    }
+}
 
-                let nib = NibbleSlice::from_encoded(node_key).0;
-                if !key.starts_with(&nib) {
-                    return Ok(());
+/// A key to verify with [`verify_state_proofs`], along with its expected value,
+/// `None` if the key is expected to be absent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StateProofEntry<'a> {
+    pub key: &'a [u8],
+    pub value: Option<&'a [u8]>,
+}
+
+/// Verifies the existence or absence of all `entries` in the trie under
+/// `state_root`.
+///
+/// `nodes` is the set of nodes on the paths of all the keys, in any order and
+/// each one once. Each node is hashed a single time, however many paths it is
+/// on, which makes verifying a batch of keys at the same height much cheaper
+/// than verifying them one by one.
+///
+/// The number of nodes and the length of the keys are checked against
+/// `limits` first.
+pub fn verify_state_proofs(
+    entries: &[StateProofEntry<'_>],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+    limits: &ProofLimits,
+) -> Result<(), StateProofVerificationError> {
+    limits.check_node_count(nodes.len())?;
+    for entry in entries {
+        limits.check_key(entry.key)?;
+    }
+    let mut proof_nodes = ProofNodes::new(nodes, mode)?;
+    for entry in entries {
+        let lookup_result = lookup(entry.key, state_root, |hash| proof_nodes.get(hash))?;
+        check_value(lookup_result, entry.value)?;
+    }
+    proof_nodes.finish()
+}
+
+/// A set of keys, with keys and paths in the trie given as nibbles.
+trait KeySet {
+    /// Whether some key starting with `path` is in the set.
+    fn overlaps(&self, path: &[u8]) -> bool;
+    /// Whether `key` is in the set.
+    fn contains(&self, key: &[u8]) -> bool;
+}
+
+/// The keys starting with a prefix.
+struct KeysWithPrefix(Vec<u8>);
+
+impl KeySet for KeysWithPrefix {
+    fn overlaps(&self, path: &[u8]) -> bool {
+        path.starts_with(&self.0) || self.0.starts_with(path)
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        key.starts_with(&self.0)
+    }
+}
+
+/// The keys strictly between two keys.
+struct KeysInRange {
+    start: Vec<u8>,
+    end: Vec<u8>,
+}
+
+impl KeySet for KeysInRange {
+    fn overlaps(&self, path: &[u8]) -> bool {
+        // All keys starting with `path` are at most `start` when `path` is
+        // lower than `start` without being a prefix of it, and all of them
+        // are at least `end` when `path` is.
+        let below_start = path < self.start.as_slice() && !self.start.starts_with(path);
+        let above_end = path >= self.end.as_slice();
+        !below_start && !above_end
+    }
+
+    fn contains(&self, key: &[u8]) -> bool {
+        self.start.as_slice() < key && key < self.end.as_slice()
+    }
+}
+
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    NibbleSlice::new(bytes).iter().collect()
+}
+
+/// Looks for a key of `keys` in the trie under `state_root`, opening every
+/// node of the trie whose subtree may hold one of them.
+///
+/// `get_node` returns the node with the given hash, along with its index in
+/// the proof. Returns `Ok(true)` as soon as a key of the set is found, and an
+/// error as soon as a path is deeper than allowed by `limits`.
+fn find_key_in<'a, F>(
+    keys: &impl KeySet,
+    state_root: &CryptoHash,
+    limits: &ProofLimits,
+    mut get_node: F,
+) -> Result<bool, StateProofVerificationError>
+where
+    F: FnMut(&CryptoHash) -> Result<(u16, &'a RawTrieNodeWithSize), StateProofVerificationError>,
+{
+    let mut pending = vec![(*state_root, Vec::new())];
+
+    while let Some((hash, path)) = pending.pop() {
+        if !keys.overlaps(&path) {
+            continue;
+        }
+        let (_, node) = get_node(&hash)?;
+        match &node.node {
+            RawTrieNode::Leaf(node_key, _, _) => {
+                let mut key = path;
+                key.extend(NibbleSlice::from_encoded(node_key).0.iter());
+                if keys.contains(&key) {
+                    return Ok(true);
                 }
-                key = key.mid(nib.len());
             }
-            RawTrieNodeWithSize {
-                node: RawTrieNode::Branch(children, node_value),
-                ..
-            } => {
-                if hash_node(node) != expected_hash {
-                    return Err(StateProofVerificationError::InvalidProofData {
-                        proof_index: node_index,
-                    });
+            RawTrieNode::Extension(node_key, child_hash) => {
+                let mut child_path = path;
+                child_path.extend(NibbleSlice::from_encoded(node_key).0.iter());
+                limits.check_nibble_depth(child_path.len())?;
+                pending.push((*child_hash, child_path));
+            }
+            RawTrieNode::Branch(children, node_value) => {
+                if node_value.is_some() && keys.contains(&path) {
+                    return Ok(true);
                 }
-
-                if key.is_empty() {
-                    match node_value {
-                        Some(_) => {
-                            return Err(StateProofVerificationError::SpecifiedKeyHasValueInState)
-                        }
-                        None => return Ok(()),
-                    }
-                }
-
-                let index = key.at(0);
-                match &children[index as usize] {
-                    Some(child_hash) => {
-                        key = key.mid(1);
-                        expected_hash = *child_hash;
-                    }
-                    None => {
-                        return Ok(());
+                limits.check_nibble_depth(path.len() + 1)?;
+                for (index, child_hash) in (0_u8..).zip(children.iter()) {
+                    if let Some(child_hash) = child_hash {
+                        let mut child_path = path.clone();
+                        child_path.push(index);
+                        pending.push((*child_hash, child_path));
                     }
                 }
             }
         }
     }
-    Err(StateProofVerificationError::InvalidProofDataLength)
+    Ok(false)
+}
+
+/// Verifies that no key of `keys` is in the trie under `state_root`, returning
+/// `Ok(false)` if one of them is.
+fn verify_no_key_in(
+    keys: &impl KeySet,
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+    limits: &ProofLimits,
+) -> Result<bool, StateProofVerificationError> {
+    limits.check_node_count(nodes.len())?;
+    let mut proof_nodes = ProofNodes::new(nodes, mode)?;
+    if find_key_in(keys, state_root, limits, |hash| proof_nodes.get(hash))? {
+        return Ok(false);
+    }
+    proof_nodes.finish()?;
+    Ok(true)
+}
+
+/// Verifies that no key starting with `prefix` is in the trie under
+/// `state_root`.
+///
+/// `nodes` is the set of nodes whose subtree may hold a key with the prefix,
+/// in any order, i.e. the nodes on the path of the prefix. Their number, and
+/// the depth of the paths opened in the trie, are checked against `limits`.
+pub fn verify_no_key_with_prefix(
+    prefix: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+    limits: &ProofLimits,
+) -> Result<(), StateProofVerificationError> {
+    limits.check_key(prefix)?;
+    let keys = KeysWithPrefix(to_nibbles(prefix));
+    match verify_no_key_in(&keys, nodes, state_root, mode, limits)? {
+        true => Ok(()),
+        false => Err(StateProofVerificationError::KeyWithSpecifiedPrefixInState),
+    }
+}
+
+/// Verifies that no key strictly between `start` and `end` is in the trie
+/// under `state_root`. `start` and `end` themselves may be in the trie.
+///
+/// `nodes` is the set of nodes whose subtree may hold a key in the range, in
+/// any order, i.e. the nodes on the paths of `start` and `end` except the
+/// ones under `end`. Their number, and the depth of the paths opened in the
+/// trie, are checked against `limits`.
+pub fn verify_no_key_in_range(
+    start: &[u8],
+    end: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+    mode: ProofMode,
+    limits: &ProofLimits,
+) -> Result<(), StateProofVerificationError> {
+    if start >= end {
+        return Err(StateProofVerificationError::InvalidKeyRange);
+    }
+    limits.check_key(start)?;
+    limits.check_key(end)?;
+    let keys = KeysInRange {
+        start: to_nibbles(start),
+        end: to_nibbles(end),
+    };
+    match verify_no_key_in(&keys, nodes, state_root, mode, limits)? {
+        true => Ok(()),
+        false => Err(StateProofVerificationError::KeyInSpecifiedRangeInState),
+    }
 }
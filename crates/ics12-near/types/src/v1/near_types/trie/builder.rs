@@ -0,0 +1,257 @@
+//! In-memory NEAR trie, to generate state proofs without a NEAR node.
+//!
+//! The trie has the exact shape, node encoding and `memory_usage` accounting
+//! of the NEAR state trie, so that its root and proofs are the ones a NEAR
+//! node would produce for the same key/value pairs.
+
+use super::super::CryptoHash;
+use super::{
+    find_key_in, lookup, nibble_slice::NibbleSlice, to_nibbles, KeySet, KeysInRange,
+    KeysWithPrefix, ProofLimits, RawTrieNode, RawTrieNodeWithSize, StateProofVerificationError,
+    ValueRef,
+};
+use alloc::{collections::BTreeMap, vec::Vec};
+
+/// Cost of a node in the `memory_usage` of NEAR trie nodes.
+const TRIE_NODE_COST: u64 = 50;
+/// Cost of a byte of an encoded node key.
+const TRIE_BYTE_OF_KEY_COST: u64 = 2;
+/// Cost of a byte of a value.
+const TRIE_BYTE_OF_VALUE_COST: u64 = 1;
+
+/// Key/value pairs stored as a NEAR trie.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct InMemoryTrie {
+    entries: BTreeMap<Vec<u8>, Vec<u8>>,
+}
+
+/// Nodes of an [`InMemoryTrie`], indexed by hash.
+struct TrieNodes {
+    root: CryptoHash,
+    nodes: BTreeMap<CryptoHash, RawTrieNodeWithSize>,
+}
+
+impl InMemoryTrie {
+    ///
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the value of `key`, returning its previous value if any.
+    pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) -> Option<Vec<u8>> {
+        self.entries.insert(key, value)
+    }
+
+    /// Removes `key` from the trie, returning its value if any.
+    pub fn remove(&mut self, key: &[u8]) -> Option<Vec<u8>> {
+        self.entries.remove(key)
+    }
+
+    ///
+    pub fn get(&self, key: &[u8]) -> Option<&[u8]> {
+        self.entries.get(key).map(Vec::as_slice)
+    }
+
+    ///
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    ///
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns the root of the trie, the default hash for an empty trie as in
+    /// NEAR.
+    pub fn root(&self) -> CryptoHash {
+        self.build().root
+    }
+
+    /// Returns the proof of the value or absence of `key`, i.e. the nodes on
+    /// its path from the root, as taken by `verify_state_proof` and
+    /// `verify_not_in_state`.
+    ///
+    /// The proof of any key is empty for an empty trie.
+    pub fn prove(&self, key: &[u8]) -> Vec<RawTrieNodeWithSize> {
+        self.prove_keys(&[key])
+    }
+
+    /// Returns the proof of the values or absence of all `keys`, i.e. the
+    /// nodes on their paths, each one once and starting with the root, as
+    /// taken by `verify_state_proofs`.
+    pub fn prove_keys(&self, keys: &[&[u8]]) -> Vec<RawTrieNodeWithSize> {
+        let trie = self.build();
+        let mut proof = Vec::new();
+        for key in keys {
+            // The lookup only fails when the trie is empty, with no root node.
+            let _ = lookup(key, &trie.root, |hash| trie.get_node(hash, &mut proof));
+        }
+        proof
+    }
+
+    /// Returns the proof that no key starting with `prefix` is in the trie,
+    /// as taken by `verify_no_key_with_prefix`.
+    pub fn prove_no_key_with_prefix(&self, prefix: &[u8]) -> Vec<RawTrieNodeWithSize> {
+        self.prove_no_key_in(&KeysWithPrefix(to_nibbles(prefix)))
+    }
+
+    /// Returns the proof that no key strictly between `start` and `end` is in
+    /// the trie, as taken by `verify_no_key_in_range`.
+    pub fn prove_no_key_in_range(&self, start: &[u8], end: &[u8]) -> Vec<RawTrieNodeWithSize> {
+        self.prove_no_key_in(&KeysInRange {
+            start: to_nibbles(start),
+            end: to_nibbles(end),
+        })
+    }
+
+    /// Returns the nodes opened when looking for a key of `keys`. If one of
+    /// them is in the trie, the proof is invalid.
+    fn prove_no_key_in(&self, keys: &impl KeySet) -> Vec<RawTrieNodeWithSize> {
+        let trie = self.build();
+        let mut proof = Vec::new();
+        // The trie is built locally, so the depth of its paths is not limited.
+        let limits = ProofLimits {
+            max_nibble_depth: usize::MAX,
+            ..ProofLimits::default()
+        };
+        let _ = find_key_in(keys, &trie.root, &limits, |hash| {
+            trie.get_node(hash, &mut proof)
+        });
+        proof
+    }
+
+    fn build(&self) -> TrieNodes {
+        let mut nodes = BTreeMap::new();
+        let entries: Vec<(Vec<u8>, &[u8])> = self
+            .entries
+            .iter()
+            .map(|(key, value)| (NibbleSlice::new(key).iter().collect(), value.as_slice()))
+            .collect();
+        let root = match entries.is_empty() {
+            true => CryptoHash::default(),
+            false => build_node(&entries, 0, &mut nodes).0,
+        };
+        TrieNodes { root, nodes }
+    }
+}
+
+impl TrieNodes {
+    /// Returns the node with the given hash, adding it to `proof` if it is not
+    /// in it yet.
+    fn get_node(
+        &self,
+        hash: &CryptoHash,
+        proof: &mut Vec<RawTrieNodeWithSize>,
+    ) -> Result<(u16, &RawTrieNodeWithSize), StateProofVerificationError> {
+        let node = self
+            .nodes
+            .get(hash)
+            .ok_or(StateProofVerificationError::MissingProofData)?;
+        if !proof.contains(node) {
+            proof.push(node.clone());
+        }
+        Ok((0, node))
+    }
+}
+
+impl FromIterator<(Vec<u8>, Vec<u8>)> for InMemoryTrie {
+    fn from_iter<T: IntoIterator<Item = (Vec<u8>, Vec<u8>)>>(iter: T) -> Self {
+        Self {
+            entries: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// Adds `node` to `nodes`, returning its hash and `memory_usage`.
+fn add_node(
+    node: RawTrieNode,
+    memory_usage: u64,
+    nodes: &mut BTreeMap<CryptoHash, RawTrieNodeWithSize>,
+) -> (CryptoHash, u64) {
+    let node = RawTrieNodeWithSize { node, memory_usage };
+    let hash = node.hash();
+    nodes.insert(hash, node);
+    (hash, memory_usage)
+}
+
+/// Returns the value reference stored in nodes for `value`, along with its
+/// `memory_usage`.
+fn value_ref(value: &[u8]) -> ((u32, CryptoHash), u64) {
+    let ValueRef { length, hash } = ValueRef::of(value);
+    let memory_usage = u64::from(length) * TRIE_BYTE_OF_VALUE_COST + TRIE_NODE_COST;
+    ((length, hash), memory_usage)
+}
+
+/// Builds the subtree holding `entries`, which are sorted, not empty and have
+/// the same first `depth` nibbles.
+fn build_node(
+    entries: &[(Vec<u8>, &[u8])],
+    depth: usize,
+    nodes: &mut BTreeMap<CryptoHash, RawTrieNodeWithSize>,
+) -> (CryptoHash, u64) {
+    if let [(key, value)] = entries {
+        let node_key = NibbleSlice::encode_nibbles(&key[depth..], true).to_vec();
+        let (value_ref, value_memory_usage) = value_ref(value);
+        let memory_usage =
+            TRIE_NODE_COST + node_key.len() as u64 * TRIE_BYTE_OF_KEY_COST + value_memory_usage;
+        return add_node(
+            RawTrieNode::Leaf(node_key, value_ref.0, value_ref.1),
+            memory_usage,
+            nodes,
+        );
+    }
+
+    // As the entries are sorted, the prefix common to the first and last ones
+    // is common to all of them.
+    let first = &entries[0].0[depth..];
+    let last = &entries[entries.len() - 1].0[depth..];
+    let common = first.iter().zip(last).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return build_branch(entries, depth, nodes);
+    }
+
+    let node_key = NibbleSlice::encode_nibbles(&first[..common], false).to_vec();
+    let (child_hash, child_memory_usage) = build_branch(entries, depth + common, nodes);
+    let memory_usage =
+        TRIE_NODE_COST + node_key.len() as u64 * TRIE_BYTE_OF_KEY_COST + child_memory_usage;
+    add_node(
+        RawTrieNode::Extension(node_key, child_hash),
+        memory_usage,
+        nodes,
+    )
+}
+
+/// Builds the branch holding `entries`, which are sorted, have the same first
+/// `depth` nibbles and differ at the next one, or end there for the first one.
+fn build_branch(
+    entries: &[(Vec<u8>, &[u8])],
+    depth: usize,
+    nodes: &mut BTreeMap<CryptoHash, RawTrieNodeWithSize>,
+) -> (CryptoHash, u64) {
+    let mut memory_usage = TRIE_NODE_COST;
+
+    let (value, mut rest) = match entries.split_first() {
+        Some(((key, value), rest)) if key.len() == depth => {
+            let (value_ref, value_memory_usage) = value_ref(value);
+            memory_usage += value_memory_usage;
+            (Some(value_ref), rest)
+        }
+        _ => (None, entries),
+    };
+
+    let mut children: [Option<CryptoHash>; 16] = Default::default();
+    while let Some((key, _)) = rest.first() {
+        let index = key[depth];
+        let count = rest
+            .iter()
+            .take_while(|(key, _)| key[depth] == index)
+            .count();
+        let (child_hash, child_memory_usage) = build_node(&rest[..count], depth + 1, nodes);
+        children[index as usize] = Some(child_hash);
+        memory_usage += child_memory_usage;
+        rest = &rest[count..];
+    }
+
+    add_node(RawTrieNode::Branch(children, value), memory_usage, nodes)
+}
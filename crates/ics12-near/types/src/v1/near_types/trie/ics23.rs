@@ -0,0 +1,222 @@
+//! Expression of NEAR trie proofs as ICS-23 proofs, for tooling which only
+//! handles ICS-23 `CommitmentProof`s.
+//!
+//! The hash of a NEAR trie node is the SHA-256 hash of its encoding, in which
+//! the hash of a child node sits between bytes that are known to the prover.
+//! A proof of a value held by a leaf is then an ICS-23 existence proof with:
+//!
+//! - a leaf op which does not hash, and whose output is the encoding of the
+//!   leaf without its `memory_usage`,
+//! - a first inner op which appends the `memory_usage` of the leaf and hashes,
+//! - an inner op for each node from the parent of the leaf up to the root,
+//!   with the encoding of the node before and after the hash of its child as
+//!   prefix and suffix.
+//!
+//! The result verifies against [`proof_spec`] and the state root with any
+//! ICS-23 verifier, within the following limits:
+//!
+//! - A NEAR leaf only holds the nibbles of the key which are not on the path
+//!   to it, so the key of the ICS-23 proof is the key of the leaf followed by
+//!   the value length, not the trie key. The trie key is bound by the path,
+//!   which ICS-23 verifiers do not check: use [`verify_existence_proof`] to
+//!   also check it.
+//! - Values held by branch nodes have no ICS-23 leaf, as the encoding of a
+//!   branch does not start with the prefix of leaves.
+//! - NEAR proves the absence of a key with the path ending before it, while
+//!   ICS-23 non-existence proofs need the neighbours of the key and children
+//!   at fixed positions in their parents, so absence can not be expressed.
+
+use super::super::{hash::sha256, CryptoHash};
+use super::{
+    verify_state_proof, ProofLimits, ProofMode, RawTrieNode, RawTrieNodeWithSize,
+    StateProofVerificationError,
+};
+use alloc::{format, vec, vec::Vec};
+use ibc_proto::ics23::{
+    commitment_proof, CommitmentProof, ExistenceProof, HashOp, InnerOp, InnerSpec, LeafOp,
+    LengthOp, ProofSpec,
+};
+
+/// Length of the `memory_usage` at the end of an encoded node.
+const MEMORY_USAGE_LENGTH: usize = 8;
+/// Length of the header of an encoded leaf or extension node, before its key.
+const KEY_HEADER_LENGTH: usize = 5;
+
+/// Returns the ICS-23 `ProofSpec` of the proofs returned by
+/// [`existence_proof`].
+pub fn proof_spec() -> ProofSpec {
+    ProofSpec {
+        leaf_spec: Some(LeafOp {
+            hash: HashOp::NoHash.into(),
+            prehash_key: HashOp::NoHash.into(),
+            prehash_value: HashOp::Sha256.into(),
+            length: LengthOp::NoPrefix.into(),
+            prefix: vec![super::LEAF_NODE],
+        }),
+        inner_spec: Some(InnerSpec {
+            child_order: (0..16).collect(),
+            // Children are 32 bytes long, but suffixes also hold the 8 bytes
+            // of the `memory_usage` of nodes.
+            child_size: MEMORY_USAGE_LENGTH as i32,
+            min_prefix_length: 0,
+            // The longest prefix is the one of an extension node with a key
+            // of the maximum length.
+            max_prefix_length: (KEY_HEADER_LENGTH + ProofLimits::DEFAULT_MAX_KEY_LENGTH) as i32,
+            empty_child: vec![],
+            hash: HashOp::Sha256.into(),
+        }),
+        // A node takes at least one nibble of the key, and the leaf has an
+        // inner op of its own.
+        max_depth: (ProofLimits::DEFAULT_MAX_NIBBLE_DEPTH + 2) as i32,
+        min_depth: 1,
+        ..Default::default()
+    }
+}
+
+/// Returns the ICS-23 existence proof of `value` for `key` in the trie under
+/// `state_root`, from `nodes`, the nodes on the path of the key in order from
+/// the root.
+pub fn existence_proof(
+    key: &[u8],
+    value: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+) -> Result<ExistenceProof, StateProofVerificationError> {
+    verify_state_proof(key, nodes, value, state_root, ProofMode::Strict)?;
+    let (leaf, parents) = nodes
+        .split_last()
+        .ok_or(StateProofVerificationError::MissingProofData)?;
+    let RawTrieNode::Leaf(leaf_key, value_length, _) = &leaf.node else {
+        return Err(StateProofVerificationError::InvalidIcs23Proof {
+            reason: "values held by branch nodes have no ICS-23 leaf".into(),
+        });
+    };
+
+    let mut ics23_key = leaf_key.clone();
+    ics23_key.extend(value_length.to_le_bytes());
+    let mut leaf_prefix = vec![super::LEAF_NODE];
+    leaf_prefix.extend((leaf_key.len() as u32).to_le_bytes());
+
+    let encoded_leaf = leaf.encode();
+    let mut path = vec![InnerOp {
+        hash: HashOp::Sha256.into(),
+        prefix: vec![],
+        suffix: encoded_leaf[encoded_leaf.len() - MEMORY_USAGE_LENGTH..].to_vec(),
+    }];
+    let mut child_hash = leaf.hash();
+    for node in parents.iter().rev() {
+        let encoded = node.encode();
+        let offset = child_offset(node, &child_hash).ok_or_else(|| {
+            StateProofVerificationError::InvalidIcs23Proof {
+                reason: format!("node is not a parent of {}", child_hash),
+            }
+        })?;
+        path.push(InnerOp {
+            hash: HashOp::Sha256.into(),
+            prefix: encoded[..offset].to_vec(),
+            suffix: encoded[offset + 32..].to_vec(),
+        });
+        child_hash = node.hash();
+    }
+
+    Ok(ExistenceProof {
+        key: ics23_key,
+        value: value.to_vec(),
+        leaf: Some(LeafOp {
+            prefix: leaf_prefix,
+            ..proof_spec().leaf_spec.expect("never failed")
+        }),
+        path,
+    })
+}
+
+/// Returns [`existence_proof`] as an ICS-23 `CommitmentProof`.
+pub fn commitment_proof(
+    key: &[u8],
+    value: &[u8],
+    nodes: &[RawTrieNodeWithSize],
+    state_root: &CryptoHash,
+) -> Result<CommitmentProof, StateProofVerificationError> {
+    Ok(CommitmentProof {
+        proof: Some(commitment_proof::Proof::Exist(existence_proof(
+            key, value, nodes, state_root,
+        )?)),
+    })
+}
+
+/// Returns the trie nodes of an existence proof returned by
+/// [`existence_proof`], in order from the root.
+pub fn nodes_from_existence_proof(
+    proof: &ExistenceProof,
+) -> Result<Vec<RawTrieNodeWithSize>, StateProofVerificationError> {
+    let invalid = |reason: &str| StateProofVerificationError::InvalidIcs23Proof {
+        reason: reason.into(),
+    };
+    let leaf = proof
+        .leaf
+        .as_ref()
+        .ok_or_else(|| invalid("missing leaf op"))?;
+    let spec = proof_spec().leaf_spec.expect("never failed");
+    if (leaf.hash, leaf.prehash_key, leaf.prehash_value, leaf.length)
+        != (spec.hash, spec.prehash_key, spec.prehash_value, spec.length)
+    {
+        return Err(invalid("leaf op does not follow the NEAR proof spec"));
+    }
+
+    let mut child = leaf.prefix.clone();
+    child.extend(&proof.key);
+    child.extend(sha256(&proof.value));
+    let mut nodes = Vec::with_capacity(proof.path.len());
+    for op in &proof.path {
+        if op.hash != i32::from(HashOp::Sha256) {
+            return Err(invalid("inner op does not hash with SHA-256"));
+        }
+        let mut encoded = op.prefix.clone();
+        encoded.extend(&child);
+        encoded.extend(&op.suffix);
+        let node =
+            RawTrieNodeWithSize::decode(&encoded).map_err(|_| invalid("invalid trie node"))?;
+        child = node.hash().0.to_vec();
+        nodes.push(node);
+    }
+    nodes.reverse();
+    Ok(nodes)
+}
+
+/// Verifies an existence proof returned by [`existence_proof`] for `key` and
+/// `value` in the trie under `state_root`, including the trie key that ICS-23
+/// verifiers do not check.
+pub fn verify_existence_proof(
+    key: &[u8],
+    value: &[u8],
+    proof: &ExistenceProof,
+    state_root: &CryptoHash,
+) -> Result<(), StateProofVerificationError> {
+    if proof.value != value {
+        return Err(StateProofVerificationError::InvalidIcs23Proof {
+            reason: "value of the proof does not match".into(),
+        });
+    }
+    let nodes = nodes_from_existence_proof(proof)?;
+    verify_state_proof(key, &nodes, value, state_root, ProofMode::Strict)
+}
+
+/// Returns the offset of the hash of the child `child_hash` in the encoding
+/// of `node`.
+fn child_offset(node: &RawTrieNodeWithSize, child_hash: &CryptoHash) -> Option<usize> {
+    match &node.node {
+        RawTrieNode::Leaf(..) => None,
+        RawTrieNode::Extension(key, hash) => {
+            (hash == child_hash).then_some(KEY_HEADER_LENGTH + key.len())
+        }
+        RawTrieNode::Branch(children, value) => {
+            // Node type, value length and hash, then the children bitmap.
+            let header_length = 1 + value.map_or(0, |_| 4 + 32) + 2;
+            let position = children
+                .iter()
+                .flatten()
+                .position(|hash| hash == child_hash)?;
+            Some(header_length + 32 * position)
+        }
+    }
+}
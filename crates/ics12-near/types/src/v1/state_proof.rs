@@ -0,0 +1,289 @@
+//! Defines the commitment proof format of the NEAR light client.
+
+#[cfg(feature = "std")]
+pub mod view_state;
+
+use super::{
+    error::Error,
+    near_types::{
+        account::{verify_account, Account},
+        hash::{sha256, CryptoHash},
+        trie::{verify_state_proofs, ProofLimits, ProofMode, RawTrieNodeWithSize, StateProofEntry},
+        AccountId,
+    },
+};
+use alloc::vec::Vec;
+use borsh::{to_vec, BorshDeserialize, BorshSerialize};
+use ibc_core::commitment_types::commitment::CommitmentProofBytes;
+
+/// Shard id of the proofs decoded from the legacy format, a borsh encoded
+/// `Vec<Vec<u8>>` of trie nodes, which does not carry one. The state root of
+/// such a proof may be the one of any shard.
+pub const ANY_SHARD_ID: u64 = u64::MAX;
+
+/// Proof of the existence or absence of a key in the state of a NEAR shard,
+/// carried as `CommitmentProofBytes` in IBC messages.
+///
+/// The proof is borsh encoded, and the variant of this enum is the version of
+/// the format. Proofs in the legacy format, the bare borsh encoded trie nodes,
+/// are still decoded, as version 1 proofs for [`ANY_SHARD_ID`].
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum NearStateProof {
+    V1(NearStateProofV1),
+    V2(NearStateProofV2),
+}
+
+/// Version 1 of the [`NearStateProof`] format.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct NearStateProofV1 {
+    /// Id of the shard holding the key, i.e. the index of its state root in the
+    /// `prev_state_root_of_chunks` of the header
+    pub shard_id: u64,
+    /// Trie nodes on the path of the key, each one encoded as a NEAR
+    /// `RawTrieNodeWithSize`. The first node is the state root of the shard.
+    ///
+    /// A proof for several keys holds the nodes on the paths of all of them,
+    /// each one once and in any order after the state root.
+    pub nodes: Vec<Vec<u8>>,
+}
+
+/// Version 2 of the [`NearStateProof`] format, which also proves the account
+/// of the IBC contract, in the same shard as its storage.
+#[derive(Clone, Debug, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct NearStateProofV2 {
+    pub shard_id: u64,
+    pub nodes: Vec<Vec<u8>>,
+    /// Borsh encoded `Account` of the IBC contract
+    pub account: Vec<u8>,
+    /// Trie nodes on the path of the account of the IBC contract, in order
+    /// from the state root.
+    pub account_nodes: Vec<Vec<u8>>,
+}
+
+impl NearStateProof {
+    /// Creates a proof without the account of the IBC contract.
+    pub fn new(shard_id: u64, nodes: Vec<Vec<u8>>) -> Self {
+        Self::V1(NearStateProofV1 { shard_id, nodes })
+    }
+    /// Adds the proof of the account of the IBC contract to the proof.
+    pub fn with_account_proof(self, account: Vec<u8>, account_nodes: Vec<Vec<u8>>) -> Self {
+        let (shard_id, nodes) = match self {
+            Self::V1(proof) => (proof.shard_id, proof.nodes),
+            Self::V2(proof) => (proof.shard_id, proof.nodes),
+        };
+        Self::V2(NearStateProofV2 {
+            shard_id,
+            nodes,
+            account,
+            account_nodes,
+        })
+    }
+    ///
+    pub fn version(&self) -> u8 {
+        match self {
+            Self::V1(_) => 1,
+            Self::V2(_) => 2,
+        }
+    }
+    ///
+    pub fn shard_id(&self) -> u64 {
+        match self {
+            Self::V1(proof) => proof.shard_id,
+            Self::V2(proof) => proof.shard_id,
+        }
+    }
+    ///
+    pub fn nodes(&self) -> &[Vec<u8>] {
+        match self {
+            Self::V1(proof) => &proof.nodes,
+            Self::V2(proof) => &proof.nodes,
+        }
+    }
+    ///
+    pub fn encode_vec(&self) -> Vec<u8> {
+        to_vec(self).expect("never failed")
+    }
+    /// Decodes a proof within the default [`ProofLimits`].
+    pub fn decode(bytes: &[u8]) -> Result<Self, Error> {
+        Self::decode_with_limits(bytes, &ProofLimits::default())
+    }
+    /// Decodes a proof within `limits`.
+    ///
+    /// The size of the proof is checked first, which bounds what is allocated
+    /// to decode it, then its number of nodes. The nodes themselves are
+    /// decoded by [`Self::validate_with_limits`].
+    ///
+    /// Bytes which are not a versioned proof are decoded in the legacy format.
+    pub fn decode_with_limits(bytes: &[u8], limits: &ProofLimits) -> Result<Self, Error> {
+        limits.check_total_bytes(bytes.len())?;
+        let proof = Self::try_from_slice(bytes)
+            .or_else(|_| {
+                Vec::<Vec<u8>>::try_from_slice(bytes).map(|nodes| Self::new(ANY_SHARD_ID, nodes))
+            })
+            .map_err(|_| Error::BorshDeserializeError)?;
+        let account_nodes = match &proof {
+            Self::V1(_) => 0,
+            Self::V2(proof) => proof.account_nodes.len(),
+        };
+        limits.check_node_count(proof.nodes().len().saturating_add(account_nodes))?;
+        Ok(proof)
+    }
+
+    /// Returns the hash of the first node of the proof, i.e. the state root the
+    /// proof is for.
+    pub fn state_root(&self) -> Result<CryptoHash, Error> {
+        self.nodes()
+            .first()
+            .map(|node| CryptoHash(sha256(node)))
+            .ok_or(Error::MissingProofData)
+    }
+
+    /// Checks that the proof is for the state root of its shard, taken from
+    /// the `prev_state_root_of_chunks` of a header, and returns that root.
+    ///
+    /// The state root of a proof for [`ANY_SHARD_ID`] may be the one of any
+    /// shard.
+    pub fn verify_state_root(
+        &self,
+        prev_state_root_of_chunks: &[CryptoHash],
+    ) -> Result<CryptoHash, Error> {
+        let state_root = self.state_root()?;
+        if self.shard_id() == ANY_SHARD_ID {
+            return prev_state_root_of_chunks
+                .contains(&state_root)
+                .then_some(state_root)
+                .ok_or(Error::InvalidRootHashOfProofData);
+        }
+        let state_root_of_shard = usize::try_from(self.shard_id())
+            .ok()
+            .and_then(|index| prev_state_root_of_chunks.get(index))
+            .ok_or(Error::InvalidShardIdOfProofData {
+                shard_id: self.shard_id(),
+            })?;
+        if *state_root_of_shard != state_root {
+            return Err(Error::InvalidRootHashOfProofData);
+        }
+        Ok(state_root)
+    }
+
+    /// Checks that the proof is well formed and returns its decoded trie nodes.
+    pub fn validate(&self) -> Result<Vec<RawTrieNodeWithSize>, Error> {
+        self.validate_with_limits(&ProofLimits::default())
+    }
+    /// Checks that the proof is well formed within `limits` and returns its
+    /// decoded trie nodes.
+    pub fn validate_with_limits(
+        &self,
+        limits: &ProofLimits,
+    ) -> Result<Vec<RawTrieNodeWithSize>, Error> {
+        if self.nodes().is_empty() {
+            return Err(Error::MissingProofData);
+        }
+        limits.check_node_count(self.nodes().len())?;
+        decode_nodes(self.nodes(), limits)
+    }
+
+    /// Verifies a proof for several keys against the state root of its shard,
+    /// with each entry being a key and its expected value, `None` if the key
+    /// is expected to be absent.
+    pub fn verify_entries(
+        &self,
+        entries: &[StateProofEntry<'_>],
+        prev_state_root_of_chunks: &[CryptoHash],
+    ) -> Result<(), Error> {
+        self.verify_entries_with_limits(entries, prev_state_root_of_chunks, &ProofLimits::default())
+    }
+    /// Verifies a proof for several keys as [`Self::verify_entries`], within
+    /// `limits`.
+    pub fn verify_entries_with_limits(
+        &self,
+        entries: &[StateProofEntry<'_>],
+        prev_state_root_of_chunks: &[CryptoHash],
+        limits: &ProofLimits,
+    ) -> Result<(), Error> {
+        let state_root = self.verify_state_root(prev_state_root_of_chunks)?;
+        verify_state_proofs(
+            entries,
+            &self.validate_with_limits(limits)?,
+            &state_root,
+            ProofMode::Strict,
+            limits,
+        )
+    }
+
+    /// Verifies that the code hash of the account `account_id` of the IBC
+    /// contract is `expected_code_hash` in the state under `state_root`, with
+    /// the account proof of a version 2 proof.
+    pub fn verify_contract_code_hash(
+        &self,
+        account_id: &AccountId,
+        expected_code_hash: &CryptoHash,
+        state_root: &CryptoHash,
+    ) -> Result<(), Error> {
+        self.verify_contract_code_hash_with_limits(
+            account_id,
+            expected_code_hash,
+            state_root,
+            &ProofLimits::default(),
+        )
+    }
+    /// Verifies the code hash of the IBC contract as
+    /// [`Self::verify_contract_code_hash`], with the account proof decoded
+    /// within `limits`.
+    pub fn verify_contract_code_hash_with_limits(
+        &self,
+        account_id: &AccountId,
+        expected_code_hash: &CryptoHash,
+        state_root: &CryptoHash,
+        limits: &ProofLimits,
+    ) -> Result<(), Error> {
+        let Self::V2(proof) = self else {
+            return Err(Error::MissingAccountProof);
+        };
+        limits.check_node_count(proof.account_nodes.len())?;
+        let account_nodes = decode_nodes(&proof.account_nodes, limits)?;
+        let account = Account::decode(&proof.account)?;
+        verify_account(
+            account_id,
+            Some(&account),
+            &account_nodes,
+            state_root,
+            ProofMode::Strict,
+        )?;
+        if account.code_hash != *expected_code_hash {
+            return Err(Error::MismatchContractCodeHash {
+                expected: *expected_code_hash,
+                actual: account.code_hash,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Decodes the encoded trie `nodes` of a proof.
+fn decode_nodes(
+    nodes: &[Vec<u8>],
+    limits: &ProofLimits,
+) -> Result<Vec<RawTrieNodeWithSize>, Error> {
+    (0_u16..)
+        .zip(nodes)
+        .map(|(proof_index, node)| {
+            RawTrieNodeWithSize::decode_with_limits(node, limits, proof_index)
+        })
+        .collect()
+}
+
+impl TryFrom<&CommitmentProofBytes> for NearStateProof {
+    type Error = Error;
+
+    fn try_from(value: &CommitmentProofBytes) -> Result<Self, Self::Error> {
+        Self::decode(&Vec::<u8>::from(value.clone()))
+    }
+}
+
+impl From<NearStateProof> for CommitmentProofBytes {
+    fn from(value: NearStateProof) -> Self {
+        // The encoding is never empty, as it starts with the version.
+        CommitmentProofBytes::try_from(value.encode_vec()).expect("never failed")
+    }
+}
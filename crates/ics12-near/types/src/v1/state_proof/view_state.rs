@@ -0,0 +1,138 @@
+//! Conversion of the proofs returned by the `query` RPC of NEAR nodes, with
+//! the `view_state` request type and `include_proof`, into [`NearStateProof`]s.
+
+use super::super::{
+    error::Error,
+    near_types::{
+        get_raw_prefix_for_contract_data,
+        hash::CryptoHash,
+        trie::{select_path_nodes, RawTrieNodeWithSize},
+        AccountId,
+    },
+};
+use super::NearStateProof;
+use alloc::{format, string::String, vec::Vec};
+use ibc_core::commitment_types::commitment::CommitmentProofBytes;
+use serde::Deserialize;
+
+/// Result of a `view_state` query with `include_proof`, with its entries and
+/// trie nodes decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ViewStateResult {
+    /// Entries of the contract storage under the queried prefix
+    pub values: Vec<StateItem>,
+    /// Trie nodes recorded while reading the entries, in no particular order.
+    /// They include nodes which are not on the path of a given key.
+    pub proof: Vec<RawTrieNodeWithSize>,
+}
+
+/// An entry of the storage of a contract.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StateItem {
+    /// Key of the entry, without the prefix of the contract data in the trie
+    pub key: Vec<u8>,
+    ///
+    pub value: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+struct RawViewStateResult {
+    values: Vec<RawStateItem>,
+    #[serde(default)]
+    proof: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct RawStateItem {
+    key: String,
+    value: String,
+    /// Nodes returned with each entry by older NEAR nodes, instead of with the
+    /// whole result
+    #[serde(default)]
+    proof: Vec<String>,
+}
+
+impl ViewStateResult {
+    /// Parses the `result` object of a `view_state` query response, whose
+    /// keys, values and trie nodes are base64 encoded.
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let raw: RawViewStateResult =
+            serde_json::from_str(json).map_err(|e| Error::InvalidViewStateResponse {
+                reason: format!("{}", e),
+            })?;
+        let mut values = Vec::with_capacity(raw.values.len());
+        let mut nodes = raw.proof;
+        for item in raw.values {
+            values.push(StateItem {
+                key: decode_base64("key", &item.key)?,
+                value: decode_base64("value", &item.value)?,
+            });
+            nodes.extend(item.proof);
+        }
+        let mut proof: Vec<RawTrieNodeWithSize> = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let node =
+                RawTrieNodeWithSize::decode(&decode_base64("proof", &node)?).map_err(|e| {
+                    Error::InvalidViewStateResponse {
+                        reason: format!("invalid trie node: {}", e),
+                    }
+                })?;
+            // Older NEAR nodes return the nodes shared by several entries once
+            // for each of them.
+            if !proof.contains(&node) {
+                proof.push(node);
+            }
+        }
+        Ok(Self { values, proof })
+    }
+
+    /// Returns the value of `key` among the entries of the result.
+    pub fn value(&self, key: &[u8]) -> Option<&[u8]> {
+        self.values
+            .iter()
+            .find(|item| item.key == key)
+            .map(|item| item.value.as_slice())
+    }
+
+    /// Returns the proof of the value or absence of the raw trie key
+    /// `trie_key` in shard `shard_id`, whose state root is `state_root`, the
+    /// state root of the chunk of the shard in the `prev_state_root_of_chunks`
+    /// of the header the proof is verified against.
+    ///
+    /// The proof holds the nodes on the path of the key only, in order from
+    /// the node of the state root of the chunk.
+    pub fn state_proof(
+        &self,
+        trie_key: &[u8],
+        shard_id: u64,
+        state_root: &CryptoHash,
+    ) -> Result<NearStateProof, Error> {
+        let nodes = select_path_nodes(trie_key, &self.proof, state_root)?;
+        Ok(NearStateProof::new(
+            shard_id,
+            nodes.iter().map(RawTrieNodeWithSize::encode).collect(),
+        ))
+    }
+
+    /// Returns the commitment proof of the value or absence of `key` in the
+    /// storage of the contract `account_id`, as taken by `verify_membership`
+    /// and `verify_non_membership`.
+    ///
+    /// See [`Self::state_proof`] for `shard_id` and `state_root`.
+    pub fn commitment_proof(
+        &self,
+        account_id: &AccountId,
+        key: &[u8],
+        shard_id: u64,
+        state_root: &CryptoHash,
+    ) -> Result<CommitmentProofBytes, Error> {
+        let trie_key = get_raw_prefix_for_contract_data(account_id, key);
+        Ok(self.state_proof(&trie_key, shard_id, state_root)?.into())
+    }
+}
+
+fn decode_base64(field: &str, encoded: &str) -> Result<Vec<u8>, Error> {
+    subtle_encoding::base64::decode(encoded).map_err(|e| Error::InvalidViewStateResponse {
+        reason: format!("invalid base64 {}: {}", field, e),
+    })
+}
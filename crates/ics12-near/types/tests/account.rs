@@ -0,0 +1,234 @@
+//! Verification of accounts, access keys and contract code in the state of a
+//! NEAR shard, built with the in-memory trie.
+
+use ics12_near_types::v1::near_types::account::{
+    access_key_key, account_key, contract_code_key, verify_access_key, verify_account,
+    verify_contract_code, AccessKey, AccessKeyPermission, Account, FunctionCallPermission,
+};
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::signature::{ED25519PublicKey, PublicKey};
+use ics12_near_types::v1::near_types::trie::{builder::InMemoryTrie, ProofMode};
+use ics12_near_types::v1::near_types::AccountId;
+
+const CODE: &[u8] = b"\0asm contract";
+
+fn alice() -> AccountId {
+    "alice.near".to_string()
+}
+
+fn app() -> AccountId {
+    "app.near".to_string()
+}
+
+fn account(code_hash: CryptoHash) -> Account {
+    Account {
+        amount: 10_000,
+        locked: 0,
+        code_hash,
+        storage_usage: 182,
+    }
+}
+
+fn full_access_key() -> PublicKey {
+    PublicKey::ED25519(ED25519PublicKey([7; 32]))
+}
+
+fn function_call_key() -> PublicKey {
+    PublicKey::ED25519(ED25519PublicKey([8; 32]))
+}
+
+fn function_call_access_key() -> AccessKey {
+    AccessKey {
+        nonce: 3,
+        permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+            allowance: Some(250_000_000_000_000_000_000_000),
+            receiver_id: app(),
+            method_names: vec!["ft_transfer".to_string()],
+        }),
+    }
+}
+
+/// Returns the state of the account `alice.near`, with a full access key and a
+/// function call key for `app.near`, and of the contract `app.near`.
+fn trie() -> InMemoryTrie {
+    let full_access_key_value = AccessKey {
+        nonce: 1,
+        permission: AccessKeyPermission::FullAccess,
+    };
+    [
+        (
+            account_key(&alice()),
+            borsh::to_vec(&account(CryptoHash::default())).unwrap(),
+        ),
+        (
+            access_key_key(&alice(), &full_access_key()),
+            borsh::to_vec(&full_access_key_value).unwrap(),
+        ),
+        (
+            access_key_key(&alice(), &function_call_key()),
+            borsh::to_vec(&function_call_access_key()).unwrap(),
+        ),
+        (
+            account_key(&app()),
+            borsh::to_vec(&account(CryptoHash::hash_bytes(CODE))).unwrap(),
+        ),
+        (contract_code_key(&app()), CODE.to_vec()),
+    ]
+    .into_iter()
+    .collect()
+}
+
+#[test]
+fn account_is_verified() {
+    let trie = trie();
+    let nodes = trie.prove(&account_key(&alice()));
+
+    verify_account(
+        &alice(),
+        Some(&account(CryptoHash::default())),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .unwrap();
+
+    let mut richer = account(CryptoHash::default());
+    richer.amount += 1;
+    assert!(verify_account(
+        &alice(),
+        Some(&richer),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict
+    )
+    .is_err());
+    assert!(verify_account(&alice(), None, &nodes, &trie.root(), ProofMode::Strict).is_err());
+}
+
+#[test]
+fn missing_account_is_verified() {
+    let trie = trie();
+    let bob = "bob.near".to_string();
+    let nodes = trie.prove(&account_key(&bob));
+
+    verify_account(&bob, None, &nodes, &trie.root(), ProofMode::Strict).unwrap();
+    assert!(verify_account(
+        &bob,
+        Some(&account(CryptoHash::default())),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict
+    )
+    .is_err());
+}
+
+#[test]
+fn access_key_is_verified_with_its_permission() {
+    let trie = trie();
+    let nodes = trie.prove(&access_key_key(&alice(), &function_call_key()));
+
+    verify_access_key(
+        &alice(),
+        &function_call_key(),
+        Some(&function_call_access_key()),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .unwrap();
+}
+
+#[test]
+fn access_key_with_wrong_permission_is_rejected() {
+    let trie = trie();
+    let nodes = trie.prove(&access_key_key(&alice(), &function_call_key()));
+
+    let full_access = AccessKey {
+        permission: AccessKeyPermission::FullAccess,
+        ..function_call_access_key()
+    };
+    assert!(verify_access_key(
+        &alice(),
+        &function_call_key(),
+        Some(&full_access),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .is_err());
+
+    let mut other_method = function_call_access_key();
+    if let AccessKeyPermission::FunctionCall(permission) = &mut other_method.permission {
+        permission.method_names = vec!["ft_transfer_call".to_string()];
+    }
+    assert!(verify_access_key(
+        &alice(),
+        &function_call_key(),
+        Some(&other_method),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .is_err());
+}
+
+#[test]
+fn access_key_of_other_public_key_is_rejected() {
+    let trie = trie();
+    let nodes = trie.prove(&access_key_key(&alice(), &function_call_key()));
+
+    assert!(verify_access_key(
+        &alice(),
+        &full_access_key(),
+        Some(&function_call_access_key()),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .is_err());
+
+    // An unknown key is proven absent.
+    let unknown_key = PublicKey::ED25519(ED25519PublicKey([9; 32]));
+    let nodes = trie.prove(&access_key_key(&alice(), &unknown_key));
+    verify_access_key(
+        &alice(),
+        &unknown_key,
+        None,
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .unwrap();
+}
+
+#[test]
+fn contract_code_is_verified() {
+    let trie = trie();
+    let nodes = trie.prove(&contract_code_key(&app()));
+
+    verify_contract_code(&app(), Some(CODE), &nodes, &trie.root(), ProofMode::Strict).unwrap();
+    assert!(verify_contract_code(
+        &app(),
+        Some(b"\0asm other contract"),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict
+    )
+    .is_err());
+}
+
+#[test]
+fn account_without_contract_is_verified() {
+    let trie = trie();
+    let nodes = trie.prove(&contract_code_key(&alice()));
+
+    verify_contract_code(&alice(), None, &nodes, &trie.root(), ProofMode::Strict).unwrap();
+    assert!(verify_contract_code(
+        &alice(),
+        Some(CODE),
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict
+    )
+    .is_err());
+}
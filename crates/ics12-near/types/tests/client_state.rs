@@ -0,0 +1,131 @@
+//! Encoding of the client state in the protobuf messages of
+//! `ics12-near-proto`.
+
+use core::time::Duration;
+use ibc_core::client::types::Height;
+use ibc_proto::{google::protobuf::Any, Protobuf};
+use ics12_near_proto::v1::ClientState as RawClientState;
+use ics12_near_types::v1::client_state::{ClientState, StorageKeyScheme, DEFAULT_EPOCH_DURATION};
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::trie::ProofLimits;
+
+fn client_state() -> ClientState {
+    ClientState::new_without_validation(
+        Duration::from_secs(14 * 24 * 60 * 60),
+        Height::new(0, 152336148).unwrap(),
+        1_700_000_000_000_000_000,
+    )
+}
+
+#[test]
+fn round_trips_with_every_field_set() {
+    let client_state = client_state()
+        .with_epoch_duration(Duration::from_secs(12 * 60 * 60))
+        .with_ibc_contract_account_id("ibc.testnet".to_string())
+        .with_storage_key_scheme(StorageKeyScheme::LookupMapBorsh)
+        .with_ibc_contract_code_hash(CryptoHash([7; 32]))
+        .with_proof_limits(ProofLimits {
+            max_nodes: 64,
+            max_total_bytes: 1 << 16,
+            max_key_length: 512,
+            max_nibble_depth: 1024,
+        });
+
+    let raw = RawClientState::from(client_state.clone());
+    assert_eq!(
+        raw.epoch_duration.as_ref().map(|d| d.seconds),
+        Some(12 * 60 * 60)
+    );
+    assert_eq!(raw.ibc_contract_account_id, "ibc.testnet");
+    assert_eq!(raw.storage_key_scheme, 1);
+    assert_eq!(raw.ibc_contract_code_hash, vec![7; 32]);
+    assert_eq!(raw.proof_limits.as_ref().map(|l| l.max_nodes), Some(64));
+
+    let bytes = Protobuf::<RawClientState>::encode_vec(client_state.clone());
+    let decoded = <ClientState as Protobuf<RawClientState>>::decode_vec(&bytes).unwrap();
+    assert_eq!(decoded, client_state);
+
+    let any = Any::from(client_state.clone());
+    assert_eq!(ClientState::try_from(any).unwrap(), client_state);
+}
+
+#[test]
+fn unset_fields_decode_to_defaults() {
+    let raw = RawClientState {
+        epoch_duration: None,
+        proof_limits: None,
+        ..RawClientState::from(client_state())
+    };
+
+    let decoded = ClientState::try_from(raw).unwrap();
+    assert_eq!(decoded.epoch_duration, DEFAULT_EPOCH_DURATION);
+    assert_eq!(decoded.ibc_contract_account_id, None);
+    assert_eq!(decoded.storage_key_scheme, StorageKeyScheme::PlainString);
+    assert_eq!(decoded.ibc_contract_code_hash, None);
+    assert_eq!(decoded.proof_limits, ProofLimits::default());
+}
+
+#[test]
+fn invalid_fields_are_rejected() {
+    let raw = RawClientState::from(client_state());
+
+    let unknown_scheme = RawClientState {
+        storage_key_scheme: 2,
+        ..raw.clone()
+    };
+    assert!(ClientState::try_from(unknown_scheme).is_err());
+
+    let short_code_hash = RawClientState {
+        ibc_contract_account_id: "ibc.testnet".to_string(),
+        ibc_contract_code_hash: vec![7; 31],
+        ..raw.clone()
+    };
+    assert!(ClientState::try_from(short_code_hash).is_err());
+
+    let code_hash_without_account = RawClientState {
+        ibc_contract_code_hash: vec![7; 32],
+        ..raw
+    };
+    assert!(ClientState::try_from(code_hash_without_account).is_err());
+}
+
+#[test]
+fn max_nodes_is_bounded_by_node_indices() {
+    let with_max_nodes = |max_nodes: usize| {
+        let client_state = client_state().with_proof_limits(ProofLimits {
+            max_nodes,
+            ..ProofLimits::default()
+        });
+        ClientState::try_from(RawClientState::from(client_state))
+    };
+
+    assert!(with_max_nodes(ProofLimits::MAX_NODES).is_ok());
+    assert!(with_max_nodes(ProofLimits::MAX_NODES + 1).is_err());
+
+    // Limits set directly are still bounded when checked.
+    let limits = ProofLimits {
+        max_nodes: usize::MAX,
+        ..ProofLimits::default()
+    };
+    assert!(limits.validate().is_err());
+    assert!(limits.check_node_count(ProofLimits::MAX_NODES).is_ok());
+    assert!(limits.check_node_count(ProofLimits::MAX_NODES + 1).is_err());
+}
+
+#[test]
+fn epoch_is_stale_after_two_epoch_durations() {
+    let epoch_duration = Duration::from_secs(12 * 60 * 60);
+    let client_state = client_state().with_epoch_duration(epoch_duration);
+
+    assert!(!client_state.is_epoch_stale(Duration::ZERO));
+    assert!(!client_state.is_epoch_stale(epoch_duration));
+    assert!(!client_state.is_epoch_stale(2 * epoch_duration));
+    assert!(client_state.is_epoch_stale(2 * epoch_duration + Duration::from_nanos(1)));
+}
+
+#[test]
+fn epoch_of_unbounded_duration_is_never_stale() {
+    let client_state = client_state().with_epoch_duration(Duration::MAX);
+
+    assert!(!client_state.is_epoch_stale(Duration::MAX));
+}
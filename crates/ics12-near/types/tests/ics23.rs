@@ -0,0 +1,117 @@
+//! Round trips of NEAR trie proofs through ICS-23 existence proofs, verified
+//! with the `ics23` crate.
+
+use ibc_proto::ics23::{commitment_proof, CommitmentProof};
+use ics12_near_types::v1::near_types::trie::{
+    builder::InMemoryTrie, ics23 as near_ics23, verify_state_proof, ProofMode, RawTrieNode,
+};
+
+/// Proves `key` in `trie`, round trips the proof through an ICS-23 existence
+/// proof, and returns the nodes of the proof.
+fn round_trip(trie: &InMemoryTrie, key: &[u8]) -> Vec<RawTrieNode> {
+    let value = trie.get(key).unwrap();
+    let state_root = trie.root();
+    let nodes = trie.prove(key);
+
+    let existence = near_ics23::existence_proof(key, value, &nodes, &state_root).unwrap();
+    let decoded = near_ics23::nodes_from_existence_proof(&existence).unwrap();
+    assert_eq!(decoded, nodes);
+    verify_state_proof(key, &decoded, value, &state_root, ProofMode::Strict).unwrap();
+    near_ics23::verify_existence_proof(key, value, &existence, &state_root).unwrap();
+
+    let proof = CommitmentProof {
+        proof: Some(commitment_proof::Proof::Exist(existence.clone())),
+    };
+    assert_eq!(
+        near_ics23::commitment_proof(key, value, &nodes, &state_root).unwrap(),
+        proof
+    );
+    assert!(ics23::verify_membership::<ics23::HostFunctionsManager>(
+        &proof,
+        &near_ics23::proof_spec(),
+        &state_root.0.to_vec(),
+        &existence.key,
+        value,
+    ));
+    assert!(!ics23::verify_membership::<ics23::HostFunctionsManager>(
+        &proof,
+        &near_ics23::proof_spec(),
+        &state_root.0.to_vec(),
+        &existence.key,
+        b"other value",
+    ));
+
+    nodes.iter().map(|node| node.node().clone()).collect()
+}
+
+#[test]
+fn leaf_path() {
+    let trie: InMemoryTrie = [(b"key".to_vec(), b"value".to_vec())].into_iter().collect();
+
+    let nodes = round_trip(&trie, b"key");
+    assert!(matches!(nodes.as_slice(), [RawTrieNode::Leaf(..)]));
+}
+
+#[test]
+fn extension_path() {
+    let trie: InMemoryTrie = [
+        (b"commitments/1".to_vec(), vec![1; 32]),
+        (b"commitments/2".to_vec(), vec![2; 32]),
+    ]
+    .into_iter()
+    .collect();
+
+    for key in [b"commitments/1", b"commitments/2"] {
+        let nodes = round_trip(&trie, key);
+        assert!(matches!(
+            nodes.as_slice(),
+            [
+                RawTrieNode::Extension(..),
+                RawTrieNode::Branch(..),
+                RawTrieNode::Leaf(..)
+            ]
+        ));
+    }
+}
+
+#[test]
+fn branch_path() {
+    let trie: InMemoryTrie = [
+        (vec![0x10], vec![1]),
+        (vec![0x20], vec![2; 100]),
+        (vec![0x30, 0x01], vec![3]),
+        (vec![0x30, 0x02], vec![4]),
+    ]
+    .into_iter()
+    .collect();
+
+    let nodes = round_trip(&trie, &[0x20]);
+    assert!(matches!(
+        nodes.as_slice(),
+        [RawTrieNode::Branch(..), RawTrieNode::Leaf(..)]
+    ));
+    let nodes = round_trip(&trie, &[0x30, 0x02]);
+    assert!(matches!(
+        nodes.as_slice(),
+        [
+            RawTrieNode::Branch(..),
+            RawTrieNode::Extension(..),
+            RawTrieNode::Branch(..),
+            RawTrieNode::Leaf(..)
+        ]
+    ));
+}
+
+#[test]
+fn branch_value_has_no_existence_proof() {
+    let trie: InMemoryTrie = [(vec![0x10], vec![1]), (vec![0x10, 0x20], vec![2])]
+        .into_iter()
+        .collect();
+
+    let nodes = trie.prove(&[0x10]);
+    assert!(matches!(
+        nodes.last().map(|node| node.node()),
+        Some(RawTrieNode::Branch(_, Some(_)))
+    ));
+    assert!(near_ics23::existence_proof(&[0x10], &[1], &nodes, &trie.root()).is_err());
+}
@@ -0,0 +1,235 @@
+//! Checks of the conflicting headers of a misbehaviour and of its ancestry
+//! proof.
+
+use ibc_core::host::types::identifiers::ClientId;
+use ibc_proto::Protobuf;
+use ics12_near_proto::v1::Misbehaviour as RawMisbehaviour;
+use ics12_near_types::v1::header::Header;
+use ics12_near_types::v1::misbehaviour::{AncestryProof, Misbehaviour, MisbehaviourKind};
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::{
+    BlockHeaderInnerLite, EpochId, LightClientBlock, LightClientBlockLite,
+};
+
+const EPOCH: CryptoHash = CryptoHash([1; 32]);
+const NEXT_EPOCH: CryptoHash = CryptoHash([2; 32]);
+
+fn client_id() -> ClientId {
+    "12-near-0".parse().unwrap()
+}
+
+/// Returns a header at `height` with the given parent and timestamp.
+fn header(height: u64, prev_block_hash: CryptoHash, timestamp: u64) -> Header {
+    Header {
+        light_client_block: LightClientBlock {
+            prev_block_hash,
+            next_block_inner_hash: CryptoHash([0; 32]),
+            inner_lite: BlockHeaderInnerLite {
+                height,
+                epoch_id: EpochId(EPOCH),
+                next_epoch_id: EpochId(NEXT_EPOCH),
+                prev_state_root: CryptoHash([0; 32]),
+                outcome_root: CryptoHash([0; 32]),
+                timestamp,
+                next_bp_hash: CryptoHash([3; 32]),
+                block_merkle_root: CryptoHash([0; 32]),
+            },
+            inner_rest_hash: CryptoHash([0; 32]),
+            next_bps: None,
+            approvals_after_next: vec![],
+        },
+        prev_state_root_of_chunks: vec![],
+    }
+}
+
+fn lite(header: &Header) -> LightClientBlockLite {
+    let block = &header.light_client_block;
+    LightClientBlockLite {
+        inner_lite: block.inner_lite.clone(),
+        inner_rest_hash: block.inner_rest_hash,
+        prev_block_hash: block.prev_block_hash,
+    }
+}
+
+/// Returns a header at height 10, a block of another fork at the same height,
+/// and a header at height 11 which is its child.
+fn fork() -> (Header, LightClientBlockLite, Header) {
+    let lower = header(10, CryptoHash([9; 32]), 1_000);
+    let other = lite(&header(10, CryptoHash([8; 32]), 1_000));
+    let higher = header(11, other.current_block_hash(), 2_000);
+    (lower, other, higher)
+}
+
+#[test]
+fn new_orders_headers_by_height() {
+    let (lower, _, higher) = fork();
+
+    for misbehaviour in [
+        Misbehaviour::new(client_id(), lower.clone(), higher.clone()),
+        Misbehaviour::new(client_id(), higher.clone(), lower.clone()),
+    ] {
+        assert_eq!(misbehaviour.header1(), &higher);
+        assert_eq!(misbehaviour.header2(), &lower);
+    }
+
+    // Headers at the same height keep their order.
+    let conflicting = header(10, CryptoHash([7; 32]), 1_000);
+    let misbehaviour = Misbehaviour::new(client_id(), lower.clone(), conflicting.clone());
+    assert_eq!(misbehaviour.header1(), &lower);
+    assert_eq!(misbehaviour.header2(), &conflicting);
+}
+
+#[test]
+fn raw_misbehaviour_is_normalized() {
+    let (lower, _, higher) = fork();
+    #[allow(deprecated)]
+    let raw = RawMisbehaviour {
+        client_id: client_id().to_string(),
+        header_1: Some(lower.clone().into()),
+        header_2: Some(higher.clone().into()),
+        ancestry_proof: vec![],
+    };
+
+    let misbehaviour = Misbehaviour::try_from(raw).unwrap();
+    assert_eq!(misbehaviour.header1(), &higher);
+    assert_eq!(misbehaviour.header2(), &lower);
+}
+
+#[test]
+fn ancestry_proof_of_fork_is_accepted() {
+    let (lower, other, higher) = fork();
+
+    let misbehaviour = Misbehaviour::new(client_id(), lower, higher)
+        .with_ancestry_proof(AncestryProof::PrevBlock(other));
+    misbehaviour.verify_ancestry_proof().unwrap();
+}
+
+#[test]
+fn ancestry_proof_of_ancestor_is_rejected() {
+    let (lower, _, _) = fork();
+    let higher = header(11, lower.light_client_block.current_block_hash(), 2_000);
+
+    let misbehaviour = Misbehaviour::new(client_id(), lower.clone(), higher)
+        .with_ancestry_proof(AncestryProof::PrevBlock(lite(&lower)));
+    assert!(misbehaviour.verify_ancestry_proof().is_err());
+}
+
+#[test]
+fn ancestry_proof_is_not_checked_at_same_height() {
+    let (lower, other, _) = fork();
+    let conflicting = header(10, CryptoHash([7; 32]), 1_000);
+
+    // The proof shows nothing about headers at the same height, but does not
+    // invalidate their conflict either.
+    let misbehaviour = Misbehaviour::new(client_id(), lower, conflicting)
+        .with_ancestry_proof(AncestryProof::PrevBlock(other));
+    misbehaviour.verify_ancestry_proof().unwrap();
+}
+
+#[test]
+fn raw_misbehaviour_round_trips() {
+    let (lower, other, higher) = fork();
+
+    let misbehaviour = Misbehaviour::new(client_id(), lower, higher);
+    let bytes = Protobuf::<RawMisbehaviour>::encode_vec(misbehaviour.clone());
+    assert_eq!(
+        <Misbehaviour as Protobuf<RawMisbehaviour>>::decode_vec(&bytes).unwrap(),
+        misbehaviour
+    );
+
+    let misbehaviour = misbehaviour.with_ancestry_proof(AncestryProof::PrevBlock(other));
+    let raw = RawMisbehaviour::from(misbehaviour.clone());
+    assert!(!raw.ancestry_proof.is_empty());
+    let bytes = Protobuf::<RawMisbehaviour>::encode_vec(misbehaviour.clone());
+    assert_eq!(
+        <Misbehaviour as Protobuf<RawMisbehaviour>>::decode_vec(&bytes).unwrap(),
+        misbehaviour
+    );
+}
+
+#[test]
+fn malformed_ancestry_proof_is_rejected() {
+    let (lower, other, higher) = fork();
+    let misbehaviour = Misbehaviour::new(client_id(), lower, higher)
+        .with_ancestry_proof(AncestryProof::PrevBlock(other));
+    let raw = RawMisbehaviour::from(misbehaviour);
+
+    let mut truncated = raw.clone();
+    truncated.ancestry_proof.pop();
+    assert!(Misbehaviour::try_from(truncated).is_err());
+
+    let mut unknown_variant = raw;
+    unknown_variant.ancestry_proof[0] = 0xff;
+    assert!(Misbehaviour::try_from(unknown_variant).is_err());
+}
+
+#[test]
+fn conflicting_next_block_producers_are_conflicting_epochs() {
+    let lower = header(10, CryptoHash([9; 32]), 1_000);
+    let higher = header(11, lower.light_client_block.current_block_hash(), 2_000);
+
+    let misbehaviour = Misbehaviour::new(client_id(), lower.clone(), higher.clone());
+    assert_eq!(misbehaviour.kind(), None);
+
+    let mut conflicting = higher;
+    conflicting.light_client_block.inner_lite.next_bp_hash = CryptoHash([4; 32]);
+    let misbehaviour = Misbehaviour::new(client_id(), lower, conflicting);
+    assert_eq!(
+        misbehaviour.kind(),
+        Some(MisbehaviourKind::ConflictingEpochs)
+    );
+}
+
+#[test]
+fn next_block_producers_of_other_epochs_do_not_conflict() {
+    let lower = header(10, CryptoHash([9; 32]), 1_000);
+    let mut higher = header(11, lower.light_client_block.current_block_hash(), 2_000);
+    higher.light_client_block.inner_lite.epoch_id = EpochId(NEXT_EPOCH);
+    higher.light_client_block.inner_lite.next_epoch_id = EpochId(CryptoHash([4; 32]));
+    higher.light_client_block.inner_lite.next_bp_hash = CryptoHash([4; 32]);
+
+    let misbehaviour = Misbehaviour::new(client_id(), lower, higher);
+    assert_eq!(misbehaviour.kind(), None);
+}
+
+#[test]
+fn kind_is_the_condition_which_makes_the_misbehaviour() {
+    let (lower, other, higher) = fork();
+
+    // The same header twice is evidence of nothing.
+    let misbehaviour = Misbehaviour::new(client_id(), lower.clone(), lower.clone());
+    assert_eq!(misbehaviour.kind(), None);
+
+    let conflicting = header(10, CryptoHash([7; 32]), 1_000);
+    let misbehaviour = Misbehaviour::new(client_id(), lower.clone(), conflicting);
+    assert_eq!(
+        misbehaviour.kind(),
+        Some(MisbehaviourKind::ConflictingBlocks)
+    );
+
+    // Without an ancestry proof, headers advancing normally are no evidence.
+    let misbehaviour = Misbehaviour::new(client_id(), lower.clone(), higher.clone());
+    assert_eq!(misbehaviour.kind(), None);
+    let misbehaviour = misbehaviour.with_ancestry_proof(AncestryProof::PrevBlock(other));
+    assert_eq!(misbehaviour.kind(), Some(MisbehaviourKind::Fork));
+
+    let earlier = header(11, lower.light_client_block.current_block_hash(), 1_000);
+    let misbehaviour = Misbehaviour::new(client_id(), lower, earlier);
+    assert_eq!(
+        misbehaviour.kind(),
+        Some(MisbehaviourKind::ConflictingTimestamps)
+    );
+}
+
+#[test]
+fn conflicting_epochs_take_precedence() {
+    let lower = header(10, CryptoHash([9; 32]), 1_000);
+    let mut conflicting = header(10, CryptoHash([7; 32]), 1_000);
+    conflicting.light_client_block.inner_lite.next_bp_hash = CryptoHash([4; 32]);
+
+    let misbehaviour = Misbehaviour::new(client_id(), lower, conflicting);
+    assert_eq!(
+        misbehaviour.kind(),
+        Some(MisbehaviourKind::ConflictingEpochs)
+    );
+}
@@ -0,0 +1,104 @@
+//! Decoding of state proofs, in the versioned and in the legacy format.
+
+use ics12_near_types::v1::near_types::hash::CryptoHash;
+use ics12_near_types::v1::near_types::trie::builder::InMemoryTrie;
+use ics12_near_types::v1::near_types::trie::{ProofLimits, StateProofEntry};
+use ics12_near_types::v1::state_proof::{NearStateProof, ANY_SHARD_ID};
+
+const KEY: &[u8] = b"key";
+
+fn trie() -> InMemoryTrie {
+    [
+        (KEY.to_vec(), b"value".to_vec()),
+        (b"other".to_vec(), b"x".to_vec()),
+    ]
+    .into_iter()
+    .collect()
+}
+
+fn nodes(trie: &InMemoryTrie) -> Vec<Vec<u8>> {
+    trie.prove(KEY).iter().map(|node| node.encode()).collect()
+}
+
+#[test]
+fn versioned_proof_round_trips() {
+    let proof = NearStateProof::new(1, nodes(&trie()));
+
+    assert_eq!(NearStateProof::decode(&proof.encode_vec()).unwrap(), proof);
+}
+
+#[test]
+fn legacy_proof_is_decoded_as_version_1_for_any_shard() {
+    let nodes = nodes(&trie());
+    let legacy = borsh::to_vec(&nodes).unwrap();
+
+    let proof = NearStateProof::decode(&legacy).unwrap();
+    assert_eq!(proof.version(), 1);
+    assert_eq!(proof.shard_id(), ANY_SHARD_ID);
+    assert_eq!(proof.nodes(), nodes.as_slice());
+}
+
+#[test]
+fn legacy_proof_is_decoded_within_limits() {
+    let nodes = nodes(&trie());
+    let legacy = borsh::to_vec(&nodes).unwrap();
+    let limits = ProofLimits {
+        max_nodes: nodes.len() - 1,
+        ..ProofLimits::default()
+    };
+
+    assert!(NearStateProof::decode_with_limits(&legacy, &limits).is_err());
+}
+
+#[test]
+fn malformed_proof_is_rejected() {
+    let mut legacy = borsh::to_vec(&nodes(&trie())).unwrap();
+    legacy.pop();
+
+    assert!(NearStateProof::decode(&legacy).is_err());
+}
+
+#[test]
+fn state_root_of_legacy_proof_may_be_of_any_shard() {
+    let trie = trie();
+    let proof = NearStateProof::decode(&borsh::to_vec(&nodes(&trie)).unwrap()).unwrap();
+    let other_root = CryptoHash([1; 32]);
+
+    assert_eq!(
+        proof.verify_state_root(&[other_root, trie.root()]).unwrap(),
+        trie.root()
+    );
+    assert!(proof.verify_state_root(&[other_root]).is_err());
+
+    // A versioned proof is only for the state root of its shard.
+    let proof = NearStateProof::new(0, nodes(&trie));
+    assert!(proof.verify_state_root(&[other_root, trie.root()]).is_err());
+}
+
+#[test]
+fn entries_are_verified_against_the_state_root_of_the_shard() {
+    let trie = trie();
+    let nodes: Vec<Vec<u8>> = trie
+        .prove_keys(&[KEY, b"absent"])
+        .iter()
+        .map(|node| node.encode())
+        .collect();
+    let entries = [
+        StateProofEntry {
+            key: KEY,
+            value: Some(b"value"),
+        },
+        StateProofEntry {
+            key: b"absent",
+            value: None,
+        },
+    ];
+    let other_root = CryptoHash([1; 32]);
+
+    NearStateProof::new(1, nodes.clone())
+        .verify_entries(&entries, &[other_root, trie.root()])
+        .unwrap();
+    assert!(NearStateProof::new(0, nodes)
+        .verify_entries(&entries, &[other_root, trie.root()])
+        .is_err());
+}
@@ -0,0 +1,420 @@
+//! Verification of proofs of the NEAR trie, generated by the in-memory trie.
+
+use ics12_near_types::v1::error::Error;
+use ics12_near_types::v1::near_types::trie::builder::InMemoryTrie;
+use ics12_near_types::v1::near_types::trie::{
+    get_value_ref, verify_no_key_in_range, verify_no_key_with_prefix, verify_not_in_state,
+    verify_state_proof, verify_state_proof_of_value_ref, verify_state_proofs, ProofLimits,
+    ProofMode, StateProofEntry, ValueRef,
+};
+
+const KEYS: [&str; 9] = ["a", "ab", "abc", "b", "ba", "bz", "c1", "c9", "zzzz"];
+
+fn value(key: &str) -> Vec<u8> {
+    format!("v-{key}").into_bytes()
+}
+
+fn trie() -> InMemoryTrie {
+    KEYS.iter()
+        .map(|key| (key.as_bytes().to_vec(), value(key)))
+        .collect()
+}
+
+fn entry<'a>(key: &'a str, value: Option<&'a [u8]>) -> StateProofEntry<'a> {
+    StateProofEntry {
+        key: key.as_bytes(),
+        value,
+    }
+}
+
+#[test]
+fn batch_of_present_and_absent_keys_is_verified() {
+    let trie = trie();
+    let (value_a, value_zzzz) = (value("a"), value("zzzz"));
+    let entries = [
+        entry("a", Some(&value_a)),
+        entry("c5", None),
+        entry("zzzz", Some(&value_zzzz)),
+    ];
+    let keys: Vec<&[u8]> = entries.iter().map(|entry| entry.key).collect();
+    let nodes = trie.prove_keys(&keys);
+
+    verify_state_proofs(
+        &entries,
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+        &ProofLimits::default(),
+    )
+    .unwrap();
+
+    // The nodes shared by the paths of the keys are in the proof once.
+    let separate_nodes: usize = keys.iter().map(|key| trie.prove(key).len()).sum();
+    assert!(nodes.len() < separate_nodes);
+}
+
+#[test]
+fn batch_is_verified_with_nodes_in_any_order() {
+    let trie = trie();
+    let (value_ab, value_c9) = (value("ab"), value("c9"));
+    let entries = [entry("ab", Some(&value_ab)), entry("c9", Some(&value_c9))];
+    let mut nodes = trie.prove_keys(&[b"ab", b"c9"]);
+    nodes.reverse();
+
+    verify_state_proofs(
+        &entries,
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+        &ProofLimits::default(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn batch_with_wrong_value_is_rejected() {
+    let trie = trie();
+    let (value_a, value_b) = (value("a"), value("b"));
+    let nodes = trie.prove_keys(&[b"a", b"b"]);
+
+    let entries = [entry("a", Some(&value_a)), entry("b", Some(&value_a))];
+    assert!(verify_state_proofs(
+        &entries,
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+        &ProofLimits::default(),
+    )
+    .is_err());
+
+    // A present key is not absent.
+    let entries = [entry("a", Some(&value_a)), entry("b", None)];
+    assert!(matches!(
+        verify_state_proofs(
+            &entries,
+            &nodes,
+            &trie.root(),
+            ProofMode::Strict,
+            &ProofLimits::default(),
+        ),
+        Err(Error::SpecifiedKeyHasValueInState)
+    ));
+
+    let entries = [entry("a", Some(&value_a)), entry("b", Some(&value_b))];
+    verify_state_proofs(
+        &entries,
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+        &ProofLimits::default(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn batch_without_the_path_of_a_key_is_rejected() {
+    let trie = trie();
+    let value_a = value("a");
+    let nodes = trie.prove(b"a");
+
+    let entries = [entry("a", Some(&value_a)), entry("c5", None)];
+    assert!(matches!(
+        verify_state_proofs(
+            &entries,
+            &nodes,
+            &trie.root(),
+            ProofMode::Lenient,
+            &ProofLimits::default(),
+        ),
+        Err(Error::MissingProofData)
+    ));
+}
+
+#[test]
+fn single_key_proof_is_verified_in_order() {
+    let trie = trie();
+    for key in KEYS {
+        let nodes = trie.prove(key.as_bytes());
+        verify_state_proof(
+            key.as_bytes(),
+            &nodes,
+            &value(key),
+            &trie.root(),
+            ProofMode::Strict,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn absence_of_keys_with_prefix_is_verified() {
+    let trie = trie();
+    for prefix in ["d", "ca", "abcd", "zzzzz", "y"] {
+        let nodes = trie.prove_no_key_with_prefix(prefix.as_bytes());
+        verify_no_key_with_prefix(
+            prefix.as_bytes(),
+            &nodes,
+            &trie.root(),
+            ProofMode::Strict,
+            &ProofLimits::default(),
+        )
+        .unwrap_or_else(|e| panic!("{prefix}: {e}"));
+    }
+}
+
+#[test]
+fn keys_with_prefix_are_not_absent() {
+    let trie = trie();
+    for prefix in ["c", "ab", "zz", ""] {
+        let nodes = trie.prove_no_key_with_prefix(prefix.as_bytes());
+        assert!(
+            matches!(
+                verify_no_key_with_prefix(
+                    prefix.as_bytes(),
+                    &nodes,
+                    &trie.root(),
+                    ProofMode::Strict,
+                    &ProofLimits::default(),
+                ),
+                Err(Error::KeyWithSpecifiedPrefixInState)
+            ),
+            "{prefix}"
+        );
+    }
+
+    // The proof of another prefix does not prove this one.
+    let nodes = trie.prove_no_key_with_prefix(b"d");
+    assert!(verify_no_key_with_prefix(
+        b"c",
+        &nodes,
+        &trie.root(),
+        ProofMode::Lenient,
+        &ProofLimits::default(),
+    )
+    .is_err());
+}
+
+#[test]
+fn absence_of_keys_in_range_is_verified() {
+    let trie = trie();
+    // The bounds themselves may be in the trie.
+    for (start, end) in [
+        ("c1", "c9"),
+        ("c9", "zzzz"),
+        ("a", "ab"),
+        ("", "a"),
+        ("ab", "abc"),
+    ] {
+        let nodes = trie.prove_no_key_in_range(start.as_bytes(), end.as_bytes());
+        verify_no_key_in_range(
+            start.as_bytes(),
+            end.as_bytes(),
+            &nodes,
+            &trie.root(),
+            ProofMode::Strict,
+            &ProofLimits::default(),
+        )
+        .unwrap_or_else(|e| panic!("{start}..{end}: {e}"));
+    }
+}
+
+#[test]
+fn keys_in_range_are_not_absent() {
+    let trie = trie();
+    for (start, end) in [("bz", "c9"), ("a", "abc"), ("", "zzzz")] {
+        let nodes = trie.prove_no_key_in_range(start.as_bytes(), end.as_bytes());
+        assert!(
+            matches!(
+                verify_no_key_in_range(
+                    start.as_bytes(),
+                    end.as_bytes(),
+                    &nodes,
+                    &trie.root(),
+                    ProofMode::Strict,
+                    &ProofLimits::default(),
+                ),
+                Err(Error::KeyInSpecifiedRangeInState)
+            ),
+            "{start}..{end}"
+        );
+    }
+}
+
+#[test]
+fn empty_range_is_rejected() {
+    let trie = trie();
+    for (start, end) in [("c9", "c1"), ("c1", "c1")] {
+        let nodes = trie.prove_no_key_in_range(b"c1", b"c9");
+        assert!(matches!(
+            verify_no_key_in_range(
+                start.as_bytes(),
+                end.as_bytes(),
+                &nodes,
+                &trie.root(),
+                ProofMode::Strict,
+                &ProofLimits::default(),
+            ),
+            Err(Error::InvalidKeyRange)
+        ));
+    }
+}
+
+#[test]
+fn unused_trailing_node_is_rejected_in_strict_mode() {
+    let trie = trie();
+    let mut nodes = trie.prove(b"a");
+    let unused_index = nodes.len();
+    nodes.push(trie.prove(b"zzzz").pop().unwrap());
+
+    verify_state_proof(b"a", &nodes, &value("a"), &trie.root(), ProofMode::Lenient).unwrap();
+    assert!(matches!(
+        verify_state_proof(b"a", &nodes, &value("a"), &trie.root(), ProofMode::Strict),
+        Err(Error::UnusedProofData { proof_index }) if usize::from(proof_index) == unused_index
+    ));
+
+    let mut nodes = trie.prove(b"c5");
+    nodes.push(trie.prove(b"zzzz").pop().unwrap());
+    verify_not_in_state(b"c5", &nodes, &trie.root(), ProofMode::Lenient).unwrap();
+    assert!(verify_not_in_state(b"c5", &nodes, &trie.root(), ProofMode::Strict).is_err());
+}
+
+#[test]
+fn unused_and_duplicate_nodes_of_batch_are_rejected_in_strict_mode() {
+    let trie = trie();
+    let value_a = value("a");
+    let entries = [entry("a", Some(&value_a))];
+    let nodes = trie.prove(b"a");
+
+    let mut unused = nodes.clone();
+    unused.push(trie.prove(b"zzzz").pop().unwrap());
+    verify_state_proofs(
+        &entries,
+        &unused,
+        &trie.root(),
+        ProofMode::Lenient,
+        &ProofLimits::default(),
+    )
+    .unwrap();
+    assert!(matches!(
+        verify_state_proofs(
+            &entries,
+            &unused,
+            &trie.root(),
+            ProofMode::Strict,
+            &ProofLimits::default(),
+        ),
+        Err(Error::UnusedProofData { .. })
+    ));
+
+    let mut duplicate = nodes.clone();
+    duplicate.push(nodes[0].clone());
+    verify_state_proofs(
+        &entries,
+        &duplicate,
+        &trie.root(),
+        ProofMode::Lenient,
+        &ProofLimits::default(),
+    )
+    .unwrap();
+    assert!(matches!(
+        verify_state_proofs(
+            &entries,
+            &duplicate,
+            &trie.root(),
+            ProofMode::Strict,
+            &ProofLimits::default(),
+        ),
+        Err(Error::DuplicateProofData { .. })
+    ));
+}
+
+#[test]
+fn unused_node_of_absence_proof_is_rejected_in_strict_mode() {
+    let trie = trie();
+    let mut nodes = trie.prove_no_key_with_prefix(b"d");
+    nodes.push(trie.prove(b"a").pop().unwrap());
+
+    verify_no_key_with_prefix(
+        b"d",
+        &nodes,
+        &trie.root(),
+        ProofMode::Lenient,
+        &ProofLimits::default(),
+    )
+    .unwrap();
+    assert!(verify_no_key_with_prefix(
+        b"d",
+        &nodes,
+        &trie.root(),
+        ProofMode::Strict,
+        &ProofLimits::default(),
+    )
+    .is_err());
+}
+
+#[test]
+fn value_ref_is_verified_without_the_value() {
+    let trie = trie();
+    // "ab" is held by a branch, "zzzz" by a leaf.
+    for key in ["ab", "zzzz"] {
+        let nodes = trie.prove(key.as_bytes());
+        let value_ref = ValueRef::of(&value(key));
+
+        verify_state_proof_of_value_ref(
+            key.as_bytes(),
+            &nodes,
+            &value_ref,
+            &trie.root(),
+            ProofMode::Strict,
+        )
+        .unwrap();
+        assert_eq!(
+            get_value_ref(key.as_bytes(), &nodes, &trie.root(), ProofMode::Strict).unwrap(),
+            Some(value_ref)
+        );
+        assert_eq!(value_ref.length as usize, value(key).len());
+    }
+}
+
+#[test]
+fn wrong_value_ref_is_rejected() {
+    let trie = trie();
+    let nodes = trie.prove(b"ab");
+    let value_ref = ValueRef::of(&value("ab"));
+
+    for wrong_value_ref in [
+        ValueRef::of(&value("ba")),
+        ValueRef {
+            length: value_ref.length + 1,
+            ..value_ref
+        },
+    ] {
+        assert!(verify_state_proof_of_value_ref(
+            b"ab",
+            &nodes,
+            &wrong_value_ref,
+            &trie.root(),
+            ProofMode::Strict,
+        )
+        .is_err());
+    }
+}
+
+#[test]
+fn value_ref_of_absent_key_is_rejected() {
+    let trie = trie();
+    let nodes = trie.prove(b"c5");
+
+    assert!(verify_state_proof_of_value_ref(
+        b"c5",
+        &nodes,
+        &ValueRef::of(&value("c5")),
+        &trie.root(),
+        ProofMode::Strict,
+    )
+    .is_err());
+    assert_eq!(
+        get_value_ref(b"c5", &nodes, &trie.root(), ProofMode::Strict).unwrap(),
+        None
+    );
+}